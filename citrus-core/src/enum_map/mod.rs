@@ -1,4 +1,5 @@
 mod iter;
+mod panel_kind;
 
 pub use iter::*;
 
@@ -41,6 +42,20 @@ impl<E: EnumKey<Option<T>>, T> EnumMap<E, Option<T>> {
     }
 }
 
+impl<E: EnumKey<T>, T: Clone> Clone for EnumMap<E, T> {
+    fn clone(&self) -> Self {
+        EnumMap {
+            data: E::Storage::init(|index| self.data.as_ref()[index].clone()),
+        }
+    }
+}
+
+impl<E: EnumKey<T>, T: PartialEq> PartialEq for EnumMap<E, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data.as_ref() == other.data.as_ref()
+    }
+}
+
 impl<E: EnumKey<T>, T> Index<E> for EnumMap<E, T> {
     type Output = T;
 
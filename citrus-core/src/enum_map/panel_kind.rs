@@ -0,0 +1,69 @@
+use citrus_common::PanelKind;
+use PanelKind::*;
+
+use super::EnumKey;
+
+// `EnumKey` implementation
+// Orphan rules mean we can't generate this with a macro, so we have to
+// redefine all of this. That's fine, I had to do this like this in the
+// previous crate too.
+impl<T> EnumKey<T> for PanelKind {
+    type Storage = [T; 22];
+
+    fn into_usize(kind: PanelKind) -> usize {
+        match kind {
+            Empty => 0,
+            Deck => 1,
+            Neutral => 2,
+            Home => 3,
+            Draw => 4,
+            Draw2x => 5,
+            Bonus => 6,
+            Bonus2x => 7,
+            Drop => 8,
+            Drop2x => 9,
+            Encounter => 10,
+            Encounter2x => 11,
+            Move => 12,
+            Move2x => 13,
+            Warp => 14,
+            WarpMove => 15,
+            WarpMove2x => 16,
+            Ice => 17,
+            Heal => 18,
+            Heal2x => 19,
+            Damage => 20,
+            Damage2x => 21,
+        }
+    }
+
+    fn from_usize(index: usize) -> PanelKind {
+        match index {
+            0 => Empty,
+            1 => Deck,
+            2 => Neutral,
+            3 => Home,
+            4 => Draw,
+            5 => Draw2x,
+            6 => Bonus,
+            7 => Bonus2x,
+            8 => Drop,
+            9 => Drop2x,
+            10 => Encounter,
+            11 => Encounter2x,
+            12 => Move,
+            13 => Move2x,
+            14 => Warp,
+            15 => WarpMove,
+            16 => WarpMove2x,
+            17 => Ice,
+            18 => Heal,
+            19 => Heal2x,
+            20 => Damage,
+            21 => Damage2x,
+            // according to the gaurantees documented on the trait, this should
+            // never be reachable
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Panel exits; the directions a unit may leave a panel in.
+//!
+//! citrus-common stores exits as a raw bitfield on [`Panel`], straight from
+//! the flag byte of a `.fldx` cell. The high nibble holds the exits; the low
+//! nibble holds other flags we don't touch. Everything in the editor goes
+//! through [`Exits`] instead, so this is the only place that needs to know
+//! how the bits are laid out.
+
+use citrus_common::Panel;
+use serde::{Deserialize, Serialize};
+
+use crate::enum_map::EnumKey;
+
+/// A direction out of a panel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    /// Every direction, clockwise from up.
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// The offset to the neighbouring cell in this direction.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        }
+    }
+
+    /// The direction pointing the other way.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            Direction::Up => 0x20,
+            Direction::Right => 0x40,
+            Direction::Down => 0x80,
+            Direction::Left => 0x10,
+        }
+    }
+}
+
+impl<T> EnumKey<T> for Direction {
+    type Storage = [T; 4];
+
+    fn into_usize(direction: Direction) -> usize {
+        direction as usize
+    }
+
+    fn from_usize(int: usize) -> Direction {
+        Direction::ALL[int]
+    }
+}
+
+/// A set of directions a panel can be exited in.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Exits(u8);
+
+impl Exits {
+    pub const NONE: Exits = Exits(0);
+    pub const ALL: Exits = Exits(0xf0);
+
+    /// Gets the exits of a panel.
+    pub fn of(panel: &Panel) -> Exits {
+        Exits(panel.exits & Self::ALL.0)
+    }
+
+    /// Sets the exits of a panel, keeping its other flags.
+    pub fn apply(self, panel: &mut Panel) {
+        panel.exits = (panel.exits & !Self::ALL.0) | self.0;
+    }
+
+    /// Checks if a direction is an exit.
+    pub fn contains(self, direction: Direction) -> bool {
+        self.0 & direction.bit() != 0
+    }
+
+    /// Adds or removes a direction.
+    pub fn set(&mut self, direction: Direction, exit: bool) {
+        if exit {
+            self.0 |= direction.bit();
+        } else {
+            self.0 &= !direction.bit();
+        }
+    }
+
+    /// Flips a direction.
+    pub fn toggle(&mut self, direction: Direction) {
+        self.0 ^= direction.bit();
+    }
+
+    /// Checks if there are no exits at all.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over every exit, clockwise from up.
+    pub fn iter(self) -> impl Iterator<Item = Direction> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .filter(move |&direction| self.contains(direction))
+    }
+}
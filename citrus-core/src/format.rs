@@ -0,0 +1,31 @@
+//! Field formats.
+
+use std::io::Cursor;
+
+use citrus_common::{field::Field, format::fldx};
+
+/// Decodes a field in fldx format.
+pub fn decode_fldx(bytes: &[u8]) -> Result<Field, anyhow::Error> {
+    fldx::decode(Cursor::new(bytes))
+        .map_err(|err| anyhow::anyhow!("could not decode field: {:?}", err))
+}
+
+/// Encodes a field in fldx format.
+pub fn encode_fldx(field: &Field) -> Vec<u8> {
+    let mut buf = Vec::new();
+    fldx::encode(field, &mut buf).unwrap();
+
+    buf
+}
+
+/// Decodes a field from base64-encoded fldx.
+pub fn decode_base64(code: &str, config: base64::Config) -> Result<Field, anyhow::Error> {
+    let bytes = base64::decode_config(code.trim(), config)?;
+
+    decode_fldx(&bytes)
+}
+
+/// Encodes a field as base64-encoded fldx.
+pub fn encode_base64(field: &Field, config: base64::Config) -> String {
+    base64::encode_config(encode_fldx(field), config)
+}
@@ -0,0 +1,88 @@
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+
+use citrus_common::field::Field;
+
+/// A bounded undo/redo history of fields.
+///
+/// Because fields are stored in an [`Rc`], taking a snapshot is as cheap as
+/// bumping a reference count. The field is only actually copied once it is
+/// modified through [`Rc::make_mut`].
+#[derive(Clone, Default)]
+pub struct History {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+/// A saved state of a field.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub field: Rc<Field>,
+    /// The cells whose exits were set by hand.
+    pub manual_exits: HashSet<(isize, isize)>,
+    /// The offset of the field's origin at the time the snapshot was taken.
+    ///
+    /// This is used to keep the view still when a snapshot is restored after
+    /// the field has been resized.
+    pub origin: (isize, isize),
+}
+
+impl History {
+    /// The maximum amount of undo states kept.
+    pub const MAX_DEPTH: usize = 100;
+
+    /// Pushes a new state onto the undo stack, discarding the redo stack.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        self.redo.clear();
+        self.undo.push_back(snapshot);
+
+        // forget the oldest states
+        while self.undo.len() > Self::MAX_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Pops a state off of the undo stack, saving `current` so it can be
+    /// redone.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop_back()?;
+        self.redo.push(current);
+
+        Some(snapshot)
+    }
+
+    /// Pops a state off of the redo stack, saving `current` so it can be
+    /// undone.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push_back(current);
+
+        Some(snapshot)
+    }
+
+    /// Gets the state on top of the undo stack.
+    pub fn last(&self) -> Option<&Snapshot> {
+        self.undo.back()
+    }
+
+    /// Pops a state off of the undo stack, without saving anything to redo.
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.undo.pop_back()
+    }
+
+    /// Checks if there is anything to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Checks if there is anything to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Clears all history.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
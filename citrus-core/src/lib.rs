@@ -0,0 +1,922 @@
+//! The field editing core of Citrus.
+//!
+//! Everything here works on fields and views of them, without touching the
+//! browser, so it builds and is tested natively. The `citrus` crate is a
+//! browser frontend over this.
+
+extern crate nalgebra as na;
+
+pub mod enum_map;
+pub mod format;
+
+mod exits;
+mod history;
+mod selection;
+mod serde;
+
+pub use exits::{Direction, Exits};
+pub use history::{History, Snapshot};
+pub use selection::Selection;
+
+#[cfg(test)]
+mod tests;
+
+use std::cmp::max;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::io::Cursor;
+
+use citrus_common::{
+    field::{Field, PanelMut, PanelRef},
+    format::fldx,
+    Panel, PanelKind,
+};
+
+use na::{Matrix4, Vector2, Vector3, Vector4};
+
+pub const EXAMPLE_FIELD: &[u8] = include_bytes!("example.fldx");
+
+/// A view of a field.
+///
+/// This uses [`Rc`] for cheap cloning, and [`Rc::make_mut`] to perform
+/// operations on its containing field.
+#[derive(Clone, ::serde::Serialize, ::serde::Deserialize)]
+pub struct EditorView {
+    pub view: Matrix4<f32>,
+    #[serde(with = "serde::field")]
+    pub field: Rc<Field>,
+    #[serde(with = "serde::panel_kind")]
+    pub selected: PanelKind,
+    #[serde(skip)]
+    pub needs_center: bool,
+    /// A cell to move the view to when it is next shown.
+    #[serde(skip)]
+    pub needs_focus: Option<(usize, usize)>,
+    #[serde(skip)]
+    pub history: History,
+    #[serde(skip)]
+    pub tool: Tool,
+    #[serde(skip)]
+    pub selection: Option<Selection>,
+    /// Whether the bucket tool fills empty cells.
+    #[serde(skip)]
+    pub fill_empty: bool,
+    /// Panels that have been lifted off of the field.
+    #[serde(skip)]
+    pub floating: Option<Floating>,
+    /// Cells whose exits were set by hand, which auto-routing leaves alone.
+    #[serde(default)]
+    pub manual_exits: HashSet<(isize, isize)>,
+    /// The accumulated offset of every resize done on the field.
+    #[serde(skip)]
+    origin: (isize, isize),
+}
+
+impl EditorView {
+    pub const DEFAULT_PANEL: PanelKind = PanelKind::Neutral;
+
+    pub const INITIAL_ZOOM: f32 = 128.;
+    pub const MAX_ZOOM: f32 = Self::INITIAL_ZOOM * 2.;
+    pub const MIN_ZOOM: f32 = Self::INITIAL_ZOOM / 4.;
+
+    pub fn new_example() -> EditorView {
+        EditorView {
+            view: Matrix4::new_scaling(Self::INITIAL_ZOOM),
+            field: Rc::new(fldx::decode(Cursor::new(EXAMPLE_FIELD))
+                .expect("could not decode example field")),
+            selected: Self::DEFAULT_PANEL,
+            needs_center: true,
+            needs_focus: None,
+            history: History::default(),
+            tool: Tool::default(),
+            selection: None,
+            fill_empty: false,
+            floating: None,
+            manual_exits: HashSet::new(),
+            origin: (0, 0),
+        }
+    }
+
+    /// Creates a view of a field decoded from fldx data.
+    pub fn from_fldx(bytes: &[u8]) -> Result<EditorView, anyhow::Error> {
+        format::decode_fldx(bytes).map(EditorView::from_field)
+    }
+
+    /// Encodes the field in fldx format.
+    pub fn to_fldx(&self) -> Vec<u8> {
+        format::encode_fldx(&self.field)
+    }
+
+    /// Creates a view of a field decoded from a base64 share code.
+    pub fn from_base64(code: &str) -> Result<EditorView, anyhow::Error> {
+        Self::from_base64_config(code, base64::STANDARD)
+    }
+
+    /// Creates a view of a field decoded from a base64 share code, with a
+    /// specific base64 alphabet.
+    pub fn from_base64_config(
+        code: &str,
+        config: base64::Config,
+    ) -> Result<EditorView, anyhow::Error> {
+        format::decode_base64(code, config).map(EditorView::from_field)
+    }
+
+    /// Encodes the field as a base64 share code.
+    pub fn to_base64(&self) -> String {
+        self.to_base64_config(base64::STANDARD)
+    }
+
+    /// Encodes the field as a base64 share code, with a specific base64
+    /// alphabet.
+    pub fn to_base64_config(&self, config: base64::Config) -> String {
+        format::encode_base64(&self.field, config)
+    }
+
+    /// Creates a view of a field, centering it when it is first shown.
+    pub fn from_field(field: Field) -> EditorView {
+        EditorView {
+            view: Matrix4::new_scaling(Self::INITIAL_ZOOM),
+            field: Rc::new(field),
+            needs_center: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_empty() -> EditorView {
+        EditorView {
+            view: Matrix4::new_scaling(Self::INITIAL_ZOOM),
+            ..Default::default()
+        }
+    }
+
+    /// Borrows the field as mutable.
+    pub fn field_mut(&mut self) -> &mut Field {
+        Rc::make_mut(&mut self.field)
+    }
+
+    /// Saves the current field to the undo history.
+    ///
+    /// This should be called before every edit to the field.
+    pub fn checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.push(snapshot);
+    }
+
+    /// Finishes an edit started with [`checkpoint`](EditorView::checkpoint).
+    ///
+    /// If the field didn't change, the checkpoint is forgotten, so undoing
+    /// doesn't step through edits that did nothing. Returns `true` if the
+    /// field changed.
+    pub fn end_edit(&mut self) -> bool {
+        let unchanged = self
+            .history
+            .last()
+            .map(|snapshot| {
+                Rc::ptr_eq(&snapshot.field, &self.field)
+                    && snapshot.manual_exits == self.manual_exits
+            })
+            .unwrap_or(false);
+
+        if unchanged {
+            self.history.pop();
+        }
+
+        !unchanged
+    }
+
+    /// Throws away an edit started with
+    /// [`checkpoint`](EditorView::checkpoint), putting the field back the way
+    /// it was.
+    pub fn cancel_edit(&mut self) {
+        if let Some(snapshot) = self.history.pop() {
+            self.restore(snapshot);
+        }
+    }
+
+    /// Reverts the field to the last checkpoint.
+    ///
+    /// Returns `true` if there was anything to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo(self.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the last undone edit.
+    ///
+    /// Returns `true` if there was anything to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo(self.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Translates the view.
+    pub fn pan(&mut self, pan: Vector2<f32>) {
+        self.view = self
+            .view
+            .append_translation(&Vector3::new(pan.x, pan.y, 0.));
+    }
+
+    /// Scales the view by a point.
+    pub fn scale(&mut self, factor: f32, at: Vector2<f32>) {
+        let at = Vector3::new(at.x, at.y, 0.);
+
+        self.view = self
+            .view
+            .append_translation(&-at)
+            .append_scaling(factor)
+            .append_translation(&at);
+    }
+
+    /// Gets the scale value.
+    pub fn get_scale(&self) -> Vector2<f32> {
+        Vector2::new(
+            (self.view.m11.powi(2) + self.view.m12.powi(2)).sqrt(),
+            (self.view.m21.powi(2) + self.view.m22.powi(2)).sqrt(),
+        )
+    }
+
+    /// Gets the translation value.
+    pub fn get_translation(&self) -> Vector2<f32> {
+        Vector2::new(self.view.m14, self.view.m24)
+    }
+
+    /// Translates and scales the field so that it rests entirely within a
+    /// bounding box.
+    pub fn center(&mut self, bb: &Vector2<f32>) {
+        if self.field.width() > 0 && self.field.height() > 0 {
+            const MARGIN: f32 = 64.;
+
+            let field_size = Vector2::new(self.field.width() as f32, self.field.height() as f32);
+
+            let room = bb - Vector2::new(MARGIN, MARGIN);
+
+            let scale = room.component_div(&field_size);
+            let scale = scale.x.min(scale.y);
+            let scale = scale.clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+
+            let field_bb = field_size * scale;
+
+            let translate = (bb - field_bb) / 2.;
+
+            self.view = Matrix4::new_scaling(scale).append_translation(&Vector3::new(
+                translate.x,
+                translate.y,
+                0.,
+            ));
+        }
+    }
+
+    /// Translates the field so that a cell is in the middle of a bounding
+    /// box, without changing the scale.
+    pub fn focus(&mut self, cell: (usize, usize), bb: &Vector2<f32>) {
+        let cell = Vector2::new(cell.0 as f32 + 0.5, cell.1 as f32 + 0.5);
+        let translate = bb / 2. - cell.component_mul(&self.get_scale());
+
+        self.pan(translate - self.get_translation());
+    }
+
+    /// Gets a panel reference using a mouse position as a reference, scaling
+    /// the field if needed.
+    pub fn flex_mut(&mut self, pos: &Vector2<f32>) -> PanelMut {
+        let (x, y) = self.flex(pos);
+
+        self.field_mut().get_mut(x, y)
+    }
+
+    /// Paints every cell on a line between two mouse positions, scaling the
+    /// field if needed.
+    ///
+    /// The line only steps orthogonally, so painted panels are always
+    /// connected.
+    pub fn paint_line(&mut self, from: &Vector2<f32>, to: &Vector2<f32>, brush: Brush) {
+        let cells = line(self.pos(from), self.pos(to));
+
+        self.paint_cells(cells, brush);
+    }
+
+    /// Paints every selected cell, scaling the field if needed.
+    pub fn fill_selection(&mut self, brush: Brush) {
+        if let Some(selection) = self.selection {
+            self.paint_cells(selection.cells(), brush);
+        }
+    }
+
+    /// Finds the cells a bucket fill of `kind`, starting at `pos`, would
+    /// change.
+    ///
+    /// This is every cell orthogonally connected to `pos` with the same kind
+    /// of panel. Empty cells are only filled if `fill_empty` is set, and even
+    /// then, never outside of the field.
+    pub fn fill_area(
+        &self,
+        pos: (isize, isize),
+        kind: PanelKind,
+        fill_empty: bool,
+    ) -> Vec<(isize, isize)> {
+        if !in_bounds(&self.field, pos) {
+            return Vec::new();
+        }
+
+        let target = self.field.get(pos.0 as usize, pos.1 as usize).kind;
+
+        if target == kind || (target == PanelKind::Empty && !fill_empty) {
+            return Vec::new();
+        }
+
+        let (width, height) = self.field_size();
+        let mut visited = vec![false; (width * height) as usize];
+        let mut stack = vec![pos];
+        let mut area = Vec::new();
+
+        while let Some(pos) = stack.pop() {
+            if !in_bounds(&self.field, pos) {
+                continue;
+            }
+
+            let (x, y) = pos.map(|x| x as usize);
+            let index = y * width as usize + x;
+
+            if visited[index] || self.field.get(x, y).kind != target {
+                continue;
+            }
+
+            visited[index] = true;
+            area.push(pos);
+
+            stack.extend(
+                [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                    .iter()
+                    .map(|&offset| pos.add(offset)),
+            );
+        }
+
+        area
+    }
+
+    /// Finds the panel edge under a mouse position.
+    ///
+    /// Returns the cell and the direction of the edge nearest to the mouse,
+    /// or `None` if the mouse isn't near the edge of a panel.
+    pub fn edge(&self, pos: &Vector2<f32>) -> Option<((usize, usize), Direction)> {
+        // how far from the center of a panel a click counts as an edge
+        const EDGE_DISTANCE: f32 = 0.2;
+
+        let pos = self.field_pos(pos);
+        let cell = (pos.x, pos.y).map(|x| x.floor() as isize);
+
+        if !in_bounds(&self.field, cell) {
+            return None;
+        }
+
+        let (x, y) = cell.map(|x| x as usize);
+
+        if empty(self.field.get(x, y)) {
+            return None;
+        }
+
+        let dx = pos.x - pos.x.floor() - 0.5;
+        let dy = pos.y - pos.y.floor() - 0.5;
+
+        if dx.abs().max(dy.abs()) < EDGE_DISTANCE {
+            return None;
+        }
+
+        let direction = if dx.abs() > dy.abs() {
+            if dx > 0. {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if dy > 0. {
+            Direction::Down
+        } else {
+            Direction::Up
+        };
+
+        Some(((x, y), direction))
+    }
+
+    /// Flips one exit of a panel.
+    pub fn toggle_exit(&mut self, (x, y): (usize, usize), direction: Direction) {
+        let panel = self.field_mut().get_mut(x, y);
+        let mut exits = Exits::of(panel);
+
+        exits.toggle(direction);
+        exits.apply(panel);
+
+        self.manual_exits.insert((x, y).map(|x| x as isize));
+    }
+
+    /// Finds the exits auto-routing would change.
+    ///
+    /// Auto-routing gives every panel an exit towards each of its
+    /// neighbours. Only selected panels are routed if there is a selection.
+    /// Panels whose exits were set by hand are skipped, unless `force` is
+    /// set.
+    pub fn auto_route(&self, force: bool) -> Vec<((usize, usize), Exits)> {
+        let cells: Vec<_> = match self.selection {
+            Some(selection) => selection
+                .cells()
+                .filter(|&pos| in_bounds(&self.field, pos))
+                .collect(),
+            None => self
+                .field
+                .iter()
+                .map(|pos| pos.map(|x| x as isize))
+                .collect(),
+        };
+
+        let mut routes = Vec::new();
+
+        for pos in cells {
+            let (x, y) = pos.map(|x| x as usize);
+
+            if empty(self.field.get(x, y)) || (!force && self.manual_exits.contains(&pos)) {
+                continue;
+            }
+
+            let mut exits = Exits::NONE;
+
+            for &direction in Direction::ALL.iter() {
+                let neighbour = pos.add(direction.offset());
+
+                let connected = in_bounds(&self.field, neighbour) && {
+                    let (x, y) = neighbour.map(|x| x as usize);
+                    !empty(self.field.get(x, y))
+                };
+
+                exits.set(direction, connected);
+            }
+
+            if Exits::of(self.field.get(x, y)) != exits {
+                routes.push(((x, y), exits));
+            }
+        }
+
+        routes
+    }
+
+    /// Sets the exits of panels, forgetting whether they were set by hand.
+    pub fn set_exits(&mut self, routes: impl IntoIterator<Item = ((usize, usize), Exits)>) {
+        for ((x, y), exits) in routes {
+            exits.apply(self.field_mut().get_mut(x, y));
+            self.manual_exits.remove(&(x, y).map(|x| x as isize));
+        }
+    }
+
+    /// Copies the selected cells into a new field.
+    ///
+    /// Cells outside of the field are copied as empty panels.
+    pub fn copy_selection(&self) -> Option<Field> {
+        let selection = self.selection?;
+        let field = &self.field;
+
+        Some(Field::new_iter((0..selection.height).map(move |y| {
+            (0..selection.width).map(move |x| {
+                let pos = (selection.x + x, selection.y + y);
+
+                if in_bounds(field, pos) {
+                    let (x, y) = pos.map(|x| x as usize);
+                    field.get(x, y).clone()
+                } else {
+                    Panel::EMPTY
+                }
+            })
+        })))
+    }
+
+    /// Copies the selected cells, along with which of them had their exits
+    /// set by hand.
+    pub fn copy_floating(&self) -> Option<Floating> {
+        let selection = self.selection?;
+        let pos = (selection.x, selection.y);
+
+        let manual_exits = self
+            .manual_exits
+            .iter()
+            .filter(|&&cell| selection.contains(cell))
+            .map(|&cell| cell.sub(pos))
+            .collect();
+
+        Some(Floating {
+            panels: self.copy_selection()?,
+            pos,
+            manual_exits,
+        })
+    }
+
+    /// Lifts the selected panels off of the field, so they can be moved.
+    pub fn lift_selection(&mut self) {
+        if let Some(floating) = self.copy_floating() {
+            self.fill_selection(Brush::Erase);
+            self.floating = Some(floating);
+        }
+    }
+
+    /// Moves the lifted panels, along with the selection.
+    pub fn move_floating(&mut self, offset: (isize, isize)) {
+        if let Some(floating) = self.floating.as_mut() {
+            floating.pos = floating.pos.add(offset);
+            self.selection = self.selection.map(|s| s.offset(offset));
+        }
+    }
+
+    /// Places the lifted panels back onto the field.
+    pub fn drop_floating(&mut self) {
+        if let Some(floating) = self.floating.take() {
+            self.stamp(&floating);
+        }
+    }
+
+    /// Places every non-empty floating panel onto the field, scaling the
+    /// field if needed.
+    pub fn stamp(&mut self, floating: &Floating) {
+        let origin = self.origin;
+
+        for (x, y) in floating.panels.iter() {
+            let panel = floating.panels.get(x, y);
+
+            if empty(panel) {
+                continue;
+            }
+
+            let manual = floating.manual_exits.contains(&(x, y).map(|x| x as isize));
+
+            // an earlier panel may have flexed the field
+            let cell = (x, y)
+                .map(|x| x as isize)
+                .add(floating.pos)
+                .add(self.origin.sub(origin));
+            let (x, y) = self.flex_cell(cell);
+
+            *self.field_mut().get_mut(x, y) = panel.clone();
+
+            // the exits came with the panel
+            let cell = (x, y).map(|x| x as isize);
+
+            if manual {
+                self.manual_exits.insert(cell);
+            } else {
+                self.manual_exits.remove(&cell);
+            }
+        }
+    }
+
+    /// Collapses the field into the smallest bounding box it can.
+    pub fn collapse(&mut self) {
+        // get bounds
+        let left_bound = self
+            .field
+            .columns_iter()
+            .position(|mut x| !x.all(empty))
+            .unwrap_or(0);
+        let right_bound = self
+            .field
+            .columns_iter()
+            .rposition(|mut x| !x.all(empty))
+            .map(|x| x + 1)
+            .unwrap_or(0);
+        let top_bound = self
+            .field
+            .rows_iter()
+            .position(|mut x| !x.all(empty))
+            .unwrap_or(0);
+        let bottom_bound = self
+            .field
+            .rows_iter()
+            .rposition(|mut x| !x.all(empty))
+            .map(|x| x + 1)
+            .unwrap_or(0);
+
+        // resize field to fit
+        self.resize_field(
+            (right_bound - left_bound, bottom_bound - top_bound).map(|x| x as isize),
+            (left_bound, top_bound).map(|x| -(x as isize)),
+        );
+    }
+
+    fn field_size(&self) -> (isize, isize) {
+        (self.field.width(), self.field.height()).map(|x| x as isize)
+    }
+
+    fn flex(&mut self, pos: &Vector2<f32>) -> (usize, usize) {
+        let pos = self.pos(pos);
+
+        self.flex_cell(pos)
+    }
+
+    fn flex_cell(&mut self, pos: (isize, isize)) -> (usize, usize) {
+        if in_bounds(&self.field, pos) {
+            pos.map(|x| x as usize)
+        } else {
+            // flex the field
+            // get an offset of the field
+            let offset = pos.map(|x| max(-x, 0));
+
+            // get resize
+            let resize = pos.apply(self.field_size(), |x, s| max(x + 1 - s, 0));
+            let resize = resize.add(offset);
+
+            let new_size = self.field_size().add(resize);
+
+            // do resize
+            self.resize_field(new_size, offset);
+
+            // there should be no negative indices anymore
+            pos.add(offset.map(|x| x as isize)).map(|x| x as usize)
+        }
+    }
+
+    fn resize_field(&mut self, size: (isize, isize), offset: (isize, isize)) {
+        debug_assert!(size.0 >= 0, "size cannot be negative");
+        debug_assert!(size.1 >= 0, "size cannot be negative");
+
+        // if the dimensions won't change anything, no point in making any
+        // changes
+        if size == self.field_size() && offset == (0, 0) {
+            return;
+        }
+
+        let field = &self.field;
+        let field = Field::new_iter((0..size.1).map(move |y| {
+            (0..size.0).map(move |x| {
+                let pos = (x, y).sub(offset);
+
+                if in_bounds(field, pos) {
+                    let (x, y) = pos.map(|x| x as usize);
+                    field.get(x, y).clone()
+                } else {
+                    Panel::EMPTY
+                }
+            })
+        }));
+        *Rc::make_mut(&mut self.field) = field;
+
+        self.shift(offset);
+
+        // translate field
+        self.view =
+            self.view
+                .prepend_translation(&-Vector3::new(offset.0 as f32, offset.1 as f32, 0.));
+    }
+
+    /// Paints every cell in field coordinates, scaling the field if needed.
+    pub fn paint_cells(&mut self, cells: impl IntoIterator<Item = (isize, isize)>, brush: Brush) {
+        let origin = self.origin;
+
+        for cell in cells {
+            // an earlier cell may have flexed the field
+            let cell = cell.add(self.origin.sub(origin));
+
+            self.paint_cell(cell, brush);
+        }
+    }
+
+    fn paint_cell(&mut self, pos: (isize, isize), brush: Brush) {
+        // check before borrowing the field mutably, so that painting over a
+        // panel with itself doesn't copy the field
+        match brush {
+            Brush::Place(kind) => {
+                let (x, y) = self.flex_cell(pos);
+
+                if self.field.get(x, y).kind != kind {
+                    self.field_mut().get_mut(x, y).kind = kind;
+                    // this is a different panel now
+                    self.manual_exits.remove(&(x, y).map(|x| x as isize));
+                }
+            }
+            Brush::Erase => {
+                if in_bounds(&self.field, pos) {
+                    let (x, y) = pos.map(|x| x as usize);
+
+                    if !empty(self.field.get(x, y)) {
+                        *self.field_mut().get_mut(x, y) = Panel::EMPTY;
+                    }
+
+                    self.manual_exits.remove(&pos);
+                }
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            field: self.field.clone(),
+            manual_exits: self.manual_exits.clone(),
+            origin: self.origin,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        // the field may have been resized since the snapshot was taken, so
+        // the view needs to be moved to keep the panels in place
+        let offset = snapshot.origin.sub(self.origin);
+
+        self.view =
+            self.view
+                .prepend_translation(&-Vector3::new(offset.0 as f32, offset.1 as f32, 0.));
+
+        self.field = snapshot.field;
+        self.shift(offset);
+
+        // these were saved where the field was, so they don't need shifting
+        self.manual_exits = snapshot.manual_exits;
+    }
+
+    /// Moves everything in field coordinates along with the field's origin.
+    fn shift(&mut self, offset: (isize, isize)) {
+        self.origin = self.origin.add(offset);
+        self.selection = self.selection.map(|s| s.offset(offset));
+
+        if let Some(floating) = self.floating.as_mut() {
+            floating.pos = floating.pos.add(offset);
+        }
+
+        if !self.manual_exits.is_empty() {
+            self.manual_exits = self
+                .manual_exits
+                .drain()
+                .map(|cell| cell.add(offset))
+                .collect();
+        }
+    }
+
+    /// Gets the cell under a mouse position, in field coordinates.
+    pub fn pos(&self, pos: &Vector2<f32>) -> (isize, isize) {
+        let pos = self.field_pos(pos);
+
+        (pos.x, pos.y).map(|x| x.floor() as isize)
+    }
+
+    /// Gets the exact point under a mouse position, in field coordinates.
+    fn field_pos(&self, pos: &Vector2<f32>) -> Vector2<f32> {
+        let inverse = self.view.try_inverse().unwrap() * Vector4::new(pos.x, pos.y, 1., 1.);
+
+        Vector2::new(inverse.x, inverse.y)
+    }
+}
+
+/// An editing tool.
+#[derive(Clone, Copy, PartialEq, Default, Debug, ::serde::Serialize, ::serde::Deserialize)]
+pub enum Tool {
+    /// Paints panels.
+    #[default]
+    Brush,
+    /// Selects rectangles of panels.
+    Select,
+    /// Fills connected panels of the same kind.
+    Bucket,
+    /// Toggles the exits of panels by clicking their edges.
+    Exits,
+}
+
+/// Panels that have been lifted off of the field.
+#[derive(Clone)]
+pub struct Floating {
+    pub panels: Field,
+    /// The position of the top left corner, in field coordinates.
+    pub pos: (isize, isize),
+    /// The panels whose exits were set by hand, relative to the top left
+    /// corner.
+    pub manual_exits: HashSet<(isize, isize)>,
+}
+
+impl Floating {
+    /// Creates floating panels at the origin, with none of their exits set
+    /// by hand.
+    pub fn new(panels: Field) -> Floating {
+        Floating {
+            panels,
+            pos: (0, 0),
+            manual_exits: HashSet::new(),
+        }
+    }
+}
+
+/// What to paint onto a field.
+#[derive(Clone, Copy)]
+pub enum Brush {
+    /// Changes the kind of a panel, creating it if it doesn't exist.
+    Place(PanelKind),
+    /// Removes a panel.
+    Erase,
+}
+
+impl Default for EditorView {
+    fn default() -> EditorView {
+        EditorView {
+            field: Rc::new(Field::new()),
+            selected: EditorView::DEFAULT_PANEL,
+            view: Matrix4::identity(),
+            needs_center: false,
+            needs_focus: None,
+            history: History::default(),
+            tool: Tool::default(),
+            selection: None,
+            fill_empty: false,
+            floating: None,
+            manual_exits: HashSet::new(),
+            origin: (0, 0),
+        }
+    }
+}
+
+impl PartialEq for EditorView {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.field, &other.field)
+            & (self.selected == other.selected)
+            & (self.view == other.view)
+    }
+}
+
+trait TupleExt<T>
+where
+    Self: Sized,
+{
+    fn map<F, U>(self, mapper: F) -> (U, U)
+    where
+        F: FnMut(T) -> U;
+
+    fn apply<F, V, U>(self, other: (V, V), apply: F) -> (U, U)
+    where
+        F: FnMut(T, V) -> U;
+
+    fn add(self, other: (T, T)) -> (T::Output, T::Output)
+    where
+        T: std::ops::Add,
+    {
+        self.apply(other, std::ops::Add::add)
+    }
+
+    fn sub(self, other: (T, T)) -> (T::Output, T::Output)
+    where
+        T: std::ops::Sub,
+    {
+        self.apply(other, std::ops::Sub::sub)
+    }
+}
+
+impl<T> TupleExt<T> for (T, T) {
+    fn map<F, U>(self, mut mapper: F) -> (U, U)
+    where
+        F: FnMut(T) -> U,
+    {
+        (mapper(self.0), mapper(self.1))
+    }
+
+    fn apply<F, V, U>(self, other: (V, V), mut apply: F) -> (U, U)
+    where
+        F: FnMut(T, V) -> U,
+    {
+        (apply(self.0, other.0), apply(self.1, other.1))
+    }
+}
+
+/// Walks the cells on a line between two cells, stepping only orthogonally.
+fn line(from: (isize, isize), to: (isize, isize)) -> Vec<(isize, isize)> {
+    let (dx, dy) = to.sub(from).map(isize::abs);
+    let (sx, sy) = to.sub(from).map(isize::signum);
+
+    let mut cells = Vec::with_capacity((dx + dy + 1) as usize);
+    let mut pos = from;
+    let (mut ix, mut iy) = (0, 0);
+
+    cells.push(pos);
+
+    while ix < dx || iy < dy {
+        // step along whichever axis the line crosses a cell border on first
+        if (1 + 2 * ix) * dy < (1 + 2 * iy) * dx {
+            pos.0 += sx;
+            ix += 1;
+        } else {
+            pos.1 += sy;
+            iy += 1;
+        }
+
+        cells.push(pos);
+    }
+
+    cells
+}
+
+fn in_bounds(field: &Field, pos: (isize, isize)) -> bool {
+    if pos.0 >= 0 && pos.1 >= 0 {
+        (pos.0 as usize) < field.width() && (pos.1 as usize) < field.height()
+    } else {
+        false
+    }
+}
+
+fn empty(panel: PanelRef) -> bool {
+    panel.kind == PanelKind::Empty
+}
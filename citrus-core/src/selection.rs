@@ -0,0 +1,54 @@
+use super::TupleExt as _;
+
+/// A rectangular selection of cells.
+///
+/// Selections are in field coordinates, and may extend outside of the field.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Selection {
+    pub x: isize,
+    pub y: isize,
+    pub width: isize,
+    pub height: isize,
+}
+
+impl Selection {
+    /// Creates a selection spanning two cells, inclusive.
+    pub fn from_corners(a: (isize, isize), b: (isize, isize)) -> Selection {
+        let (x, y) = a.apply(b, isize::min);
+        let (far_x, far_y) = a.apply(b, isize::max);
+
+        Selection {
+            x,
+            y,
+            width: far_x - x + 1,
+            height: far_y - y + 1,
+        }
+    }
+
+    /// Checks if a cell is in the selection.
+    pub fn contains(&self, pos: (isize, isize)) -> bool {
+        (self.x..self.x + self.width).contains(&pos.0)
+            && (self.y..self.y + self.height).contains(&pos.1)
+    }
+
+    /// Offsets this selection.
+    pub fn offset(self, offset: (isize, isize)) -> Selection {
+        Selection {
+            x: self.x + offset.0,
+            y: self.y + offset.1,
+            ..self
+        }
+    }
+
+    /// Iterates over every cell in the selection, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = (isize, isize)> {
+        let Selection {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| (x, y)))
+    }
+}
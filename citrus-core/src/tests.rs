@@ -0,0 +1,464 @@
+use std::cell::Cell;
+
+use crate::enum_map::EnumMap;
+use crate::{Brush, Direction, EditorView, Exits, Selection};
+use citrus_common::PanelKind;
+use na::{Matrix4, Vector2};
+
+#[test]
+pub fn test_enum_map() {
+    // test allocation
+    let mut panel_map = EnumMap::<PanelKind, String>::new(|kind| match kind {
+        PanelKind::Bonus => String::from("awesome"),
+        _ => String::new(),
+    });
+
+    assert_eq!(panel_map[PanelKind::Bonus], "awesome");
+    assert_ne!(panel_map[PanelKind::Drop], "awesome");
+
+    // test saving to field
+    panel_map[PanelKind::Draw] = String::from("pretty awesome");
+
+    assert_eq!(panel_map[PanelKind::Bonus], "awesome");
+    assert_ne!(panel_map[PanelKind::Drop], "awesome");
+    assert_eq!(panel_map[PanelKind::Draw], "pretty awesome");
+}
+
+#[test]
+pub fn test_enum_map_safety() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    // we use a special type to tell us if the the item was successfully
+    // dropped
+    struct DropTest<'a>(&'a Cell<usize>);
+
+    impl<'a> Drop for DropTest<'a> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drop_count = Cell::new(0);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        EnumMap::<PanelKind, DropTest>::new(|kind| match kind {
+            PanelKind::Drop2x => panic!("should be removed imo"),
+            _ => DropTest(&drop_count),
+        });
+    }));
+
+    assert!(result.is_err());
+    assert!(drop_count.get() > 0);
+}
+
+#[test]
+pub fn test_enum_map_iter() {
+    let panel_map = EnumMap::<PanelKind, u8>::new(|kind| kind as u8);
+
+    for (kind, value) in panel_map.iter() {
+        println!("{:?}: {:X}", kind, value);
+    }
+}
+
+#[test]
+pub fn test_undo_redo() {
+    let mut view = EditorView::default();
+
+    // place a panel behind the origin, which shifts the field
+    view.checkpoint();
+    view.flex_mut(&Vector2::new(-0.5, -0.5)).kind = PanelKind::Bonus;
+
+    assert_eq!(view.field.width(), 1);
+    assert_ne!(view.view, Matrix4::identity());
+
+    // undoing should put the view back where it was
+    assert!(view.undo());
+    assert_eq!(view.field.width(), 0);
+    assert_eq!(view.view, Matrix4::identity());
+    assert!(!view.undo());
+
+    assert!(view.redo());
+    assert_eq!(view.field.get(0, 0).kind, PanelKind::Bonus);
+    assert!(!view.redo());
+}
+
+#[test]
+pub fn test_end_edit() {
+    let mut view = EditorView::default();
+
+    view.checkpoint();
+    view.paint_cells(vec![(0, 0)], Brush::Place(PanelKind::Bonus));
+    assert!(view.end_edit());
+
+    // painting a panel over itself changes nothing, so it can't be undone
+    view.checkpoint();
+    view.paint_cells(vec![(0, 0)], Brush::Place(PanelKind::Bonus));
+    assert!(!view.end_edit());
+
+    assert!(view.undo());
+    assert_eq!(view.field.width(), 0);
+    assert!(!view.undo());
+
+    // cancelling puts the field back, without anything to redo
+    assert!(view.redo());
+    view.checkpoint();
+    view.paint_cells(vec![(1, 0)], Brush::Place(PanelKind::Drop));
+    view.cancel_edit();
+
+    assert_eq!(view.field.width(), 1);
+    assert_eq!(view.field.get(0, 0).kind, PanelKind::Bonus);
+    assert!(!view.redo());
+}
+
+#[test]
+pub fn test_paint_line() {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(3.5, 2.5),
+        Brush::Place(PanelKind::Draw),
+    );
+
+    assert_eq!(view.field.width(), 4);
+    assert_eq!(view.field.height(), 3);
+
+    // the line should be connected orthogonally, so 3 + 2 steps
+    let painted = view
+        .field
+        .iter()
+        .filter(|&(x, y)| view.field.get(x, y).kind == PanelKind::Draw)
+        .count();
+
+    assert_eq!(painted, 6);
+
+    // erasing should never grow the field
+    view.paint_line(
+        &Vector2::new(-5.5, 0.5),
+        &Vector2::new(0.5, 0.5),
+        Brush::Erase,
+    );
+
+    assert_eq!(view.field.width(), 4);
+    assert_eq!(view.field.get(0, 0).kind, PanelKind::Empty);
+}
+
+#[test]
+pub fn test_move_selection() {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(1.5, 0.5),
+        Brush::Place(PanelKind::Drop),
+    );
+
+    // move both panels up and to the left, past the origin
+    view.selection = Some(Selection::from_corners((0, 0), (1, 0)));
+    view.lift_selection();
+    view.move_floating((-1, -1));
+    view.drop_floating();
+    view.collapse();
+
+    assert_eq!(view.field.width(), 2);
+    assert_eq!(view.field.height(), 1);
+
+    // the selection follows the panels, even when the field is shifted
+    let selection = view.selection.unwrap();
+    assert_eq!((selection.x, selection.y), (0, 0));
+    assert_eq!(view.field.get(1, 0).kind, PanelKind::Drop);
+}
+
+#[test]
+pub fn test_fill_area() {
+    let mut view = EditorView::default();
+
+    // a 3x3 field with a draw panel in the middle of an L of bonus panels
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(0.5, 2.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+    view.paint_line(
+        &Vector2::new(0.5, 2.5),
+        &Vector2::new(2.5, 2.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+    view.paint_line(
+        &Vector2::new(1.5, 1.5),
+        &Vector2::new(1.5, 1.5),
+        Brush::Place(PanelKind::Draw),
+    );
+
+    assert_eq!(view.fill_area((0, 0), PanelKind::Drop, false).len(), 5);
+    // filling with the same kind does nothing
+    assert!(view.fill_area((0, 0), PanelKind::Bonus, false).is_empty());
+
+    // empty cells only fill when asked to, and never outside of the field
+    assert!(view.fill_area((2, 0), PanelKind::Drop, false).is_empty());
+    assert_eq!(view.fill_area((2, 0), PanelKind::Drop, true).len(), 3);
+    assert!(view.fill_area((5, 0), PanelKind::Drop, true).is_empty());
+
+    // erasing is filling with empty panels
+    assert_eq!(view.fill_area((0, 0), PanelKind::Empty, false).len(), 5);
+    assert!(view.fill_area((2, 0), PanelKind::Empty, false).is_empty());
+}
+
+#[test]
+pub fn test_toggle_exit() {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(0.5, 0.5),
+        Brush::Place(PanelKind::Neutral),
+    );
+
+    // near the right edge of the panel
+    let (cell, direction) = view.edge(&Vector2::new(0.9, 0.6)).unwrap();
+    assert_eq!((cell, direction), ((0, 0), Direction::Right));
+
+    view.toggle_exit(cell, direction);
+    let exits = Exits::of(view.field.get(0, 0));
+    assert!(exits.contains(Direction::Right));
+    assert_eq!(exits.iter().count(), 1);
+
+    // the middle of a panel and empty cells have no edges
+    assert!(view.edge(&Vector2::new(0.5, 0.5)).is_none());
+    assert!(view.edge(&Vector2::new(1.9, 0.5)).is_none());
+}
+
+#[test]
+pub fn test_example_exits() {
+    // the example field is one loop; check a few of its arrows so the bit
+    // layout in `Exits` stays in step with what citrus-common decodes
+    let view = EditorView::new_example();
+    let exits = |x, y| Exits::of(view.field.get(x, y)).iter().collect::<Vec<_>>();
+
+    assert_eq!(exits(5, 3), [Direction::Right]);
+    assert_eq!(exits(7, 3), [Direction::Down]);
+    assert_eq!(exits(6, 10), [Direction::Left]);
+    assert_eq!(exits(3, 10), [Direction::Up]);
+    assert_eq!(exits(9, 4), [Direction::Right, Direction::Down]);
+    assert_eq!(exits(5, 10), [Direction::Up, Direction::Left]);
+    assert!(exits(8, 2).is_empty());
+
+    // writing the exits back leaves the other flags alone
+    let mut panel = view.field.get(5, 3).clone();
+    let flags = panel.exits;
+    Exits::of(&panel).apply(&mut panel);
+    assert_eq!(panel.exits, flags);
+}
+
+#[test]
+pub fn test_manual_exits() {
+    let mut view = EditorView::default();
+
+    view.paint_cells(vec![(0, 0), (1, 0)], Brush::Place(PanelKind::Neutral));
+
+    view.checkpoint();
+    view.toggle_exit((0, 0), Direction::Right);
+    assert!(view.manual_exits.contains(&(0, 0)));
+
+    // undoing takes the flag back with the exit
+    assert!(view.undo());
+    assert!(view.manual_exits.is_empty());
+    assert!(view.redo());
+    assert!(view.manual_exits.contains(&(0, 0)));
+
+    // the flag moves with its panel
+    view.selection = Some(Selection::from_corners((0, 0), (0, 0)));
+    view.lift_selection();
+    view.move_floating((2, 0));
+    view.drop_floating();
+
+    assert_eq!(view.manual_exits.len(), 1);
+    assert!(view.manual_exits.contains(&(2, 0)));
+
+    // and goes away once it's a different panel
+    view.paint_cells(vec![(2, 0)], Brush::Place(PanelKind::Bonus));
+    assert!(view.manual_exits.is_empty());
+}
+
+#[test]
+pub fn test_auto_route() {
+    let mut view = EditorView::default();
+
+    // an L of three panels
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(1.5, 0.5),
+        Brush::Place(PanelKind::Neutral),
+    );
+    view.paint_line(
+        &Vector2::new(1.5, 1.5),
+        &Vector2::new(1.5, 1.5),
+        Brush::Place(PanelKind::Neutral),
+    );
+
+    // make the corner one-way by hand
+    view.toggle_exit((1, 0), Direction::Up);
+
+    let routes = view.auto_route(false);
+    assert_eq!(routes.len(), 2);
+    view.set_exits(routes);
+
+    let exits = |view: &EditorView, x, y| Exits::of(view.field.get(x, y));
+
+    assert_eq!(
+        exits(&view, 0, 0).iter().collect::<Vec<_>>(),
+        [Direction::Right]
+    );
+    assert_eq!(
+        exits(&view, 1, 1).iter().collect::<Vec<_>>(),
+        [Direction::Up]
+    );
+    assert_eq!(
+        exits(&view, 1, 0).iter().collect::<Vec<_>>(),
+        [Direction::Up]
+    );
+
+    // forcing reroutes the corner too
+    view.set_exits(view.auto_route(true));
+    assert_eq!(
+        exits(&view, 1, 0).iter().collect::<Vec<_>>(),
+        [Direction::Down, Direction::Left]
+    );
+    assert!(view.auto_route(true).is_empty());
+}
+
+#[test]
+pub fn test_focus() {
+    let mut view = EditorView::default();
+    let bb = Vector2::new(800., 600.);
+
+    view.scale(2., Vector2::new(30., 40.));
+    view.pan(Vector2::new(-50., 70.));
+    let scale = view.get_scale();
+
+    view.focus((3, 2), &bb);
+
+    // the cell is in the middle, and the scale is left alone
+    assert_eq!(view.pos(&(bb / 2.)), (3, 2));
+    assert_eq!(view.get_scale(), scale);
+}
+
+#[test]
+pub fn test_pos() {
+    let view = EditorView {
+        view: Matrix4::new_scaling(10.),
+        ..EditorView::default()
+    };
+
+    assert_eq!(view.pos(&Vector2::new(5., 5.)), (0, 0));
+    assert_eq!(view.pos(&Vector2::new(25., 15.)), (2, 1));
+    // cells off of the field still have a position
+    assert_eq!(view.pos(&Vector2::new(-5., -15.)), (-1, -2));
+}
+
+#[test]
+pub fn test_view_transform() {
+    let mut view = EditorView {
+        view: Matrix4::new_scaling(10.),
+        ..EditorView::default()
+    };
+
+    view.pan(Vector2::new(20., 30.));
+    assert_eq!(view.get_translation(), Vector2::new(20., 30.));
+    assert_eq!(view.pos(&Vector2::new(25., 35.)), (0, 0));
+
+    // scaling keeps the point it scales by in place
+    let at = Vector2::new(45., 55.);
+    let before = view.field_pos(&at);
+
+    view.scale(2., at);
+    assert_eq!(view.get_scale(), Vector2::new(20., 20.));
+    assert!((view.field_pos(&at) - before).norm() < 1e-4);
+
+    // centering puts the middle of the field in the middle of the box
+    view.resize_field((4, 2), (0, 0));
+    view.center(&Vector2::new(800., 600.));
+
+    let middle = view.field_pos(&Vector2::new(400., 300.));
+    assert!((middle - Vector2::new(2., 1.)).norm() < 1e-4);
+}
+
+#[test]
+pub fn test_flex() {
+    let mut view = EditorView {
+        view: Matrix4::new_scaling(10.),
+        ..EditorView::default()
+    };
+
+    // an empty field grows to fit
+    assert_eq!(view.flex(&Vector2::new(25., 15.)), (2, 1));
+    assert_eq!((view.field.width(), view.field.height()), (3, 2));
+
+    // growing up and to the left moves the field, and the view with it
+    assert_eq!(view.flex(&Vector2::new(-5., -15.)), (0, 0));
+    assert_eq!((view.field.width(), view.field.height()), (4, 4));
+
+    assert_eq!(view.pos(&Vector2::new(-5., -15.)), (0, 0));
+    assert_eq!(view.pos(&Vector2::new(25., 15.)), (3, 3));
+
+    // cells on the field don't change it
+    assert_eq!(view.flex(&Vector2::new(15., 5.)), (2, 2));
+    assert_eq!((view.field.width(), view.field.height()), (4, 4));
+}
+
+#[test]
+pub fn test_resize_field() {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(1.5, 0.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+    view.resize_field((4, 3), (1, 1));
+
+    let kind = |view: &EditorView, x, y| view.field.get(x, y).kind;
+
+    assert_eq!((view.field.width(), view.field.height()), (4, 3));
+    assert_eq!(kind(&view, 0, 0), PanelKind::Empty);
+    assert_eq!(kind(&view, 1, 1), PanelKind::Bonus);
+    assert_eq!(kind(&view, 2, 1), PanelKind::Bonus);
+    // the view follows the panels
+    assert_eq!(view.pos(&Vector2::new(0.5, 0.5)), (1, 1));
+
+    // shrinking cuts off whatever doesn't fit
+    view.resize_field((2, 3), (-2, 0));
+
+    assert_eq!((view.field.width(), view.field.height()), (2, 3));
+    assert_eq!(kind(&view, 0, 1), PanelKind::Bonus);
+    assert_eq!(kind(&view, 1, 1), PanelKind::Empty);
+}
+
+#[test]
+pub fn test_collapse() {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(1.5, 1.5),
+        &Vector2::new(2.5, 1.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+    assert_eq!((view.field.width(), view.field.height()), (3, 2));
+
+    view.collapse();
+
+    assert_eq!((view.field.width(), view.field.height()), (2, 1));
+    assert_eq!(view.field.get(0, 0).kind, PanelKind::Bonus);
+    assert_eq!(view.field.get(1, 0).kind, PanelKind::Bonus);
+    assert_eq!(view.pos(&Vector2::new(1.5, 1.5)), (0, 0));
+
+    // nothing is left of a field without panels
+    view.paint_line(
+        &Vector2::new(1.5, 1.5),
+        &Vector2::new(2.5, 1.5),
+        Brush::Erase,
+    );
+    view.collapse();
+
+    assert_eq!((view.field.width(), view.field.height()), (0, 0));
+}
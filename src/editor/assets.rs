@@ -1,9 +1,16 @@
 use citrus_common::PanelKind;
 use PanelKind::*;
 
-use crate::enum_map::EnumMap;
+use web_sys::HtmlImageElement;
+
+use crate::gl::atlas::AtlasImage;
+use crate::gl::{Color, GLTexture, Rect, GL};
+
+use citrus_core::enum_map::EnumMap;
+use citrus_core::Direction;
 
 pub type PanelMap<T> = EnumMap<PanelKind, T>;
+pub type DirectionMap<T> = EnumMap<Direction, T>;
 
 macro_rules! match_img {
     { $init:ident, $( $pattern:pat => $lit:literal ),* } => {
@@ -43,69 +50,154 @@ pub fn panel_source(kind: PanelKind) -> Option<&'static str> {
     })
 }
 
-// `EnumKey` implementation
-// Orphan rules mean we can't generate this with a macro, so we have to
-// redefine all of this. That's fine, I had to do this like this in the
-// previous crate too.
-use crate::enum_map::EnumKey;
-
-impl<T> EnumKey<T> for PanelKind {
-    type Storage = [T; 22];
-
-    fn into_usize(kind: PanelKind) -> usize {
-        match kind {
-            Empty => 0,
-            Deck => 1,
-            Neutral => 2,
-            Home => 3,
-            Draw => 4,
-            Draw2x => 5,
-            Bonus => 6,
-            Bonus2x => 7,
-            Drop => 8,
-            Drop2x => 9,
-            Encounter => 10,
-            Encounter2x => 11,
-            Move => 12,
-            Move2x => 13,
-            Warp => 14,
-            WarpMove => 15,
-            WarpMove2x => 16,
-            Ice => 17,
-            Heal => 18,
-            Heal2x => 19,
-            Damage => 20,
-            Damage2x => 21,
+/// Every panel image and exit arrow, packed into one texture.
+pub struct PanelAtlas<T = GLTexture> {
+    texture: T,
+    regions: PanelMap<Option<Rect>>,
+    arrows: DirectionMap<Rect>,
+}
+
+impl PanelAtlas {
+    /// Packs panel images and the exit arrows into an atlas.
+    ///
+    /// Images that failed to load are left out.
+    ///
+    /// # Panics
+    /// Panics if any image is not complete.
+    pub fn new(gl: &GL, images: &PanelMap<Option<HtmlImageElement>>) -> PanelAtlas {
+        let loaded: Vec<_> = images
+            .iter()
+            .filter_map(|(kind, image)| Some((kind, image.as_ref()?)))
+            .filter(|(_, image)| image.width() > 0 && image.height() > 0)
+            .collect();
+        let arrows = DirectionMap::new(arrow_pixels);
+
+        // the arrows go after the panels
+        let sources: Vec<_> = loaded
+            .iter()
+            .map(|&(_, image)| AtlasImage::Element(image))
+            .chain(arrows.iter().map(|(_, pixels)| AtlasImage::Pixels {
+                width: ARROW_SIZE,
+                height: ARROW_SIZE,
+                pixels,
+            }))
+            .collect();
+
+        let (texture, rects) = gl.create_atlas(&sources);
+        let (panel_rects, arrow_rects) = rects.split_at(loaded.len());
+
+        let mut regions = PanelMap::new_empty();
+
+        for (&(kind, _), &rect) in loaded.iter().zip(panel_rects) {
+            regions[kind] = Some(rect);
+        }
+
+        // a direction map iterates in the order directions are declared
+        let arrows = DirectionMap::new(|direction| arrow_rects[direction as usize]);
+
+        PanelAtlas {
+            texture,
+            regions,
+            arrows,
+        }
+    }
+}
+
+impl<T> PanelAtlas<T> {
+    /// Creates an atlas out of a texture that is already packed, and where
+    /// each panel kind's image and each arrow is in it, in pixels.
+    pub fn from_parts(
+        texture: T,
+        regions: PanelMap<Option<Rect>>,
+        arrows: DirectionMap<Rect>,
+    ) -> PanelAtlas<T> {
+        PanelAtlas {
+            texture,
+            regions,
+            arrows,
         }
     }
 
-    fn from_usize(index: usize) -> PanelKind {
-        match index {
-            0 => Empty,
-            1 => Deck,
-            2 => Neutral,
-            3 => Home,
-            4 => Draw,
-            5 => Draw2x,
-            6 => Bonus,
-            7 => Bonus2x,
-            8 => Drop,
-            9 => Drop2x,
-            10 => Encounter,
-            11 => Encounter2x,
-            12 => Move,
-            13 => Move2x,
-            14 => Warp,
-            15 => WarpMove,
-            16 => WarpMove2x,
-            17 => Ice,
-            18 => Heal,
-            19 => Heal2x,
-            20 => Damage,
-            21 => Damage2x,
-            // according to the gaurantees documented on the trait, this should
-            // never be reachable
-            _ => unreachable!(),
+    /// Gets the texture every panel is in.
+    pub fn texture(&self) -> &T {
+        &self.texture
+    }
+
+    /// Gets where a panel kind's image is in the texture, in pixels.
+    ///
+    /// Returns `None` if the panel kind has no image.
+    pub fn region(&self, kind: PanelKind) -> Option<Rect> {
+        self.regions[kind]
+    }
+
+    /// Gets where the arrow for an exit is in the texture, in pixels.
+    pub fn arrow(&self, direction: Direction) -> Rect {
+        self.arrows[direction]
+    }
+}
+
+/// Gets a panel kind by its position in the panel selector.
+///
+/// Only panels with an image are shown, in the order of a [`PanelMap`].
+pub fn selectable_panel(index: usize) -> Option<PanelKind> {
+    PanelMap::new(|kind| kind)
+        .iter()
+        .map(|(kind, _)| kind)
+        .filter(|&kind| panel_source(kind).is_some())
+        .nth(index)
+}
+
+/// The size of an arrow image, in pixels.
+pub const ARROW_SIZE: u32 = 32;
+
+/// Draws an arrow image, as RGBA pixels.
+///
+/// There are no arrow images to load, and an arrow only takes a triangle
+/// anyway.
+pub fn arrow_pixels(direction: Direction) -> Vec<u8> {
+    const FILL: Color = Color::rgb(0xff, 0xff, 0xff);
+    const OUTLINE: Color = Color::rgb(0x20, 0x20, 0x20);
+    /// The thickness of the outline, in the unit square.
+    const OUTLINE_WIDTH: f32 = 0.12;
+
+    let mut pixels = Vec::with_capacity((ARROW_SIZE * ARROW_SIZE * 4) as usize);
+
+    for y in 0..ARROW_SIZE {
+        for x in 0..ARROW_SIZE {
+            // sample the middle of the pixel
+            let u = (x as f32 + 0.5) / ARROW_SIZE as f32;
+            let v = (y as f32 + 0.5) / ARROW_SIZE as f32;
+
+            // rotate so the arrow always points up
+            let (u, v) = match direction {
+                Direction::Up => (u, v),
+                Direction::Down => (u, 1. - v),
+                Direction::Left => (v, u),
+                Direction::Right => (v, 1. - u),
+            };
+
+            let color = if in_arrow(u, v, OUTLINE_WIDTH) {
+                FILL
+            } else if in_arrow(u, v, 0.) {
+                OUTLINE
+            } else {
+                Color::ALPHA
+            };
+
+            pixels.extend_from_slice(&[color.red(), color.green(), color.blue(), color.alpha()]);
         }
     }
+
+    pixels
+}
+
+/// Checks if a point in the unit square is in an upwards triangle, shrunk
+/// by `inset`.
+fn in_arrow(u: f32, v: f32, inset: f32) -> bool {
+    // the tip is sharp, so it has to move further in than the base to keep
+    // the outline roughly even
+    let top = inset * 2.;
+    let bottom = 1. - inset;
+
+    (top..bottom).contains(&v) && (u - 0.5).abs() <= (v - top) / 2.
 }
@@ -0,0 +1,292 @@
+//! Remappable keyboard shortcuts.
+
+pub mod panel;
+
+pub use panel::KeymapPanel;
+
+use serde::{Deserialize, Serialize};
+use web_sys::{console, KeyboardEvent};
+use yew::services::storage::{Area, StorageService};
+
+use std::fmt::{self, Display, Formatter};
+
+use super::{Direction, Tool};
+use crate::format::Ron;
+
+/// Something a key can do in the editor.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Command {
+    /// Selects a panel kind by its position in the panel selector.
+    SelectPanel(usize),
+    SelectTool(Tool),
+    /// Moves the view towards a direction.
+    Pan(Direction),
+    ZoomIn,
+    ZoomOut,
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    Delete,
+    Cancel,
+    /// Saves the field to a file.
+    Save,
+}
+
+impl Command {
+    /// Every command, in the order they are listed for remapping.
+    pub fn all() -> Vec<Command> {
+        let tools = [Tool::Brush, Tool::Select, Tool::Bucket, Tool::Exits];
+
+        let mut commands: Vec<_> = (0..10).map(Command::SelectPanel).collect();
+        commands.extend(tools.iter().map(|&tool| Command::SelectTool(tool)));
+        commands.extend(
+            Direction::ALL
+                .iter()
+                .map(|&direction| Command::Pan(direction)),
+        );
+        commands.extend_from_slice(&[
+            Command::ZoomIn,
+            Command::ZoomOut,
+            Command::Undo,
+            Command::Redo,
+            Command::Copy,
+            Command::Cut,
+            Command::Paste,
+            Command::Delete,
+            Command::Cancel,
+            Command::Save,
+        ]);
+
+        commands
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Command::SelectPanel(index) => write!(f, "Panel {}", index + 1),
+            Command::SelectTool(tool) => write!(f, "{:?} tool", tool),
+            Command::Pan(direction) => write!(f, "Pan {:?}", direction),
+            Command::ZoomIn => write!(f, "Zoom in"),
+            Command::ZoomOut => write!(f, "Zoom out"),
+            Command::Undo => write!(f, "Undo"),
+            Command::Redo => write!(f, "Redo"),
+            Command::Copy => write!(f, "Copy"),
+            Command::Cut => write!(f, "Cut"),
+            Command::Paste => write!(f, "Paste"),
+            Command::Delete => write!(f, "Delete selection"),
+            Command::Cancel => write!(f, "Cancel"),
+            Command::Save => write!(f, "Save file"),
+        }
+    }
+}
+
+/// A key, along with the modifiers that have to be held with it.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Binding {
+    /// The key, as given by `KeyboardEvent.key`, in lowercase.
+    pub key: String,
+    /// Whether control (or command, on Mac) is held.
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl Binding {
+    /// Creates a binding for a key without any modifiers.
+    pub fn new(key: &str) -> Binding {
+        Binding {
+            key: key.to_lowercase(),
+            ctrl: false,
+            shift: false,
+        }
+    }
+
+    /// Requires control to be held.
+    pub fn ctrl(self) -> Binding {
+        Binding { ctrl: true, ..self }
+    }
+
+    /// Requires shift to be held.
+    pub fn shift(self) -> Binding {
+        Binding {
+            shift: true,
+            ..self
+        }
+    }
+
+    /// Gets the binding a key press would trigger.
+    pub fn from_event(ev: &KeyboardEvent) -> Binding {
+        Binding {
+            key: ev.key().to_lowercase(),
+            ctrl: ev.ctrl_key() || ev.meta_key(),
+            shift: ev.shift_key(),
+        }
+    }
+
+    /// Checks if the key is only a modifier, which can't be bound on its
+    /// own.
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self.key.as_str(),
+            "control" | "shift" | "alt" | "meta" | "os" | "altgraph"
+        )
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+
+        match self.key.as_str() {
+            " " => write!(f, "Space"),
+            "arrowup" => write!(f, "↑"),
+            "arrowright" => write!(f, "→"),
+            "arrowdown" => write!(f, "↓"),
+            "arrowleft" => write!(f, "←"),
+            key => {
+                // keys are saved in lowercase, but read better capitalized
+                let mut chars = key.chars();
+
+                match chars.next() {
+                    Some(first) => write!(f, "{}{}", first.to_uppercase(), chars.as_str()),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// A table of keys and the commands they do.
+///
+/// This is saved to local storage, so remapped keys are kept between visits.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    bindings: Vec<(Binding, Command)>,
+}
+
+impl Keymap {
+    const STORAGE_KEY: &'static str = "keymap";
+
+    /// Loads the keymap from local storage.
+    ///
+    /// The default keymap is used if there isn't one saved yet.
+    pub fn load() -> Keymap {
+        let storage = StorageService::new(Area::Local).unwrap();
+
+        // don't overwrite a keymap that failed to parse; it may have been
+        // edited by hand
+        if storage
+            .restore::<Result<String, anyhow::Error>>(Self::STORAGE_KEY)
+            .is_err()
+        {
+            return Keymap::default();
+        }
+
+        match storage
+            .restore::<Ron<Result<Keymap, anyhow::Error>>>(Self::STORAGE_KEY)
+            .0
+        {
+            Ok(keymap) => keymap,
+            Err(err) => {
+                console::error_1(&format!("failed to load keymap: {}", err).into());
+                Keymap::default()
+            }
+        }
+    }
+
+    /// Saves the keymap to local storage.
+    pub fn save(&self) {
+        let mut storage = StorageService::new(Area::Local).unwrap();
+
+        storage.store::<Ron<&Keymap>>(Self::STORAGE_KEY, Ron(self));
+    }
+
+    /// Gets the command bound to a key.
+    pub fn get(&self, binding: &Binding) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == binding)
+            .map(|&(_, command)| command)
+    }
+
+    /// Binds a key to a command, replacing whatever it was bound to before.
+    pub fn bind(&mut self, binding: Binding, command: Command) {
+        self.unbind(&binding);
+        self.bindings.push((binding, command));
+    }
+
+    /// Removes a key's binding.
+    pub fn unbind(&mut self, binding: &Binding) {
+        self.bindings.retain(|(bound, _)| bound != binding);
+    }
+
+    /// Gets every key bound to a command.
+    pub fn keys(&self, command: Command) -> impl Iterator<Item = &Binding> {
+        self.bindings
+            .iter()
+            .filter(move |&&(_, bound)| bound == command)
+            .map(|(binding, _)| binding)
+    }
+
+    /// Binds a key to a command, replacing every other key bound to the
+    /// command.
+    pub fn rebind(&mut self, command: Command, binding: Binding) {
+        self.bindings.retain(|&(_, bound)| bound != command);
+        self.bind(binding, command);
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let mut keymap = Keymap {
+            bindings: Vec::new(),
+        };
+
+        // number keys pick panels, with 0 as the tenth
+        for (slot, key) in "1234567890".chars().enumerate() {
+            keymap.bind(Binding::new(&key.to_string()), Command::SelectPanel(slot));
+        }
+
+        let bindings = vec![
+            (Binding::new("b"), Command::SelectTool(Tool::Brush)),
+            (Binding::new("s"), Command::SelectTool(Tool::Select)),
+            (Binding::new("g"), Command::SelectTool(Tool::Bucket)),
+            (Binding::new("e"), Command::SelectTool(Tool::Exits)),
+            (Binding::new("ArrowUp"), Command::Pan(Direction::Up)),
+            (Binding::new("ArrowRight"), Command::Pan(Direction::Right)),
+            (Binding::new("ArrowDown"), Command::Pan(Direction::Down)),
+            (Binding::new("ArrowLeft"), Command::Pan(Direction::Left)),
+            // `+` is usually shifted, except on the numpad
+            (Binding::new("+"), Command::ZoomIn),
+            (Binding::new("+").shift(), Command::ZoomIn),
+            (Binding::new("="), Command::ZoomIn),
+            (Binding::new("-"), Command::ZoomOut),
+            (Binding::new("Delete"), Command::Delete),
+            (Binding::new("Backspace"), Command::Delete),
+            (Binding::new("Escape"), Command::Cancel),
+            (Binding::new("z").ctrl(), Command::Undo),
+            (Binding::new("z").ctrl().shift(), Command::Redo),
+            (Binding::new("y").ctrl(), Command::Redo),
+            (Binding::new("c").ctrl(), Command::Copy),
+            (Binding::new("x").ctrl(), Command::Cut),
+            (Binding::new("v").ctrl(), Command::Paste),
+            (Binding::new("s").ctrl(), Command::Save),
+        ];
+
+        for (binding, command) in bindings {
+            keymap.bind(binding, command);
+        }
+
+        keymap
+    }
+}
@@ -0,0 +1,113 @@
+use yew::callback::Callback;
+use yew::prelude::*;
+
+use super::{Command, Keymap};
+use crate::util;
+
+/// Keymap editing component.
+///
+/// This only shows the keymap; the editor listens for the new key, since it
+/// already listens to every key.
+pub struct KeymapPanel {
+    link: ComponentLink<Self>,
+    props: Props,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub keymap: Keymap,
+    /// The command waiting for a new key, if any.
+    pub rebinding: Option<Command>,
+    pub onrebind: Callback<Command>,
+    pub onreset: Callback<()>,
+    pub onclose: Callback<()>,
+}
+
+pub enum Msg {
+    Rebind(Command),
+    Reset,
+    Close,
+}
+
+impl Component for KeymapPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        KeymapPanel { link, props }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        // bubble
+        match msg {
+            Msg::Rebind(command) => self.props.onrebind.emit(command),
+            Msg::Reset => self.props.onreset.emit(()),
+            Msg::Close => self.props.onclose.emit(()),
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let hint = match self.props.rebinding {
+            Some(command) => format!("Press the new key for {}, or Escape to cancel.", command),
+            None => String::from("Click a command to change its key."),
+        };
+
+        html! {
+            <div class="keymap-panel">
+                <h3>{ "Keys" }</h3>
+                <p>{ hint }</p>
+                <ul>
+                    { for Command::all().into_iter().map(|command| self.view_command(command)) }
+                </ul>
+                <a class="keymap-button"
+                   href="#"
+                   onclick=util::link_callback(&self.link, |_| Msg::Reset)>
+                    { "Reset to defaults" }
+                </a>
+                <a class="keymap-button"
+                   href="#"
+                   onclick=util::link_callback(&self.link, |_| Msg::Close)>
+                    { "Close" }
+                </a>
+            </div>
+        }
+    }
+}
+
+impl KeymapPanel {
+    fn view_command(&self, command: Command) -> Html {
+        let class = if self.props.rebinding == Some(command) {
+            "selected"
+        } else {
+            ""
+        };
+
+        let keys: Vec<_> = self
+            .props
+            .keymap
+            .keys(command)
+            .map(|binding| binding.to_string())
+            .collect();
+
+        html! {
+            <li class=class>
+                <a href="#"
+                   onclick=util::link_callback(&self.link, move |_| Msg::Rebind(command))>
+                    <span class="command">{ command }</span>
+                    <span class="keys">{ keys.join(", ") }</span>
+                </a>
+            </li>
+        }
+    }
+}
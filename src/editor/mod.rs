@@ -1,22 +1,34 @@
 pub mod assets;
+pub mod keymap;
 pub mod panel;
-pub mod view;
+pub mod scene;
 
-use wasm_bindgen::JsValue;
-use web_sys::{console, HtmlCanvasElement, HtmlImageElement};
+use gloo_events::EventListener;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{console, File, HtmlCanvasElement, HtmlImageElement};
 use yew::prelude::*;
+use yew::services::keyboard::{KeyListenerHandle, KeyboardService};
 use yew::services::render::{RenderService, RenderTask};
 use yew::services::resize::{ResizeService, ResizeTask};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
+use yew::services::DialogService;
 
-use assets::PanelMap;
-pub use view::EditorView;
+use assets::{PanelAtlas, PanelMap};
+pub use citrus_core::{Brush, Direction, EditorView, Exits, Floating, Selection, Tool};
+use keymap::{Binding, Command, Keymap, KeymapPanel};
+use scene::Scene;
 
-use crate::gl::shader::canvas::CanvasShader;
-use crate::gl::{GLTexture, GlError, GL, Rect};
-use crate::gl::util::AsyncTexture;
-use crate::util::{MouseEvent, WheelEvent};
-use citrus_common::{Panel, PanelKind};
-use na::Vector2;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use crate::format;
+use crate::gl::shader::canvas::{CanvasShader, SpriteBatch};
+use crate::gl::{GlError, GL};
+use crate::render::Renderer;
+use crate::util::{self, MouseEvent, WheelEvent};
+use citrus_common::{field::Field, PanelKind};
+use na::{Matrix4, Vector2};
 
 pub struct FieldEditor {
     link: ComponentLink<Self>,
@@ -24,33 +36,132 @@ pub struct FieldEditor {
 
     // event things
     mouse_last: MouseEvent,
+    /// Every finger on the canvas, by pointer id.
+    touches: Vec<(i32, Vector2<f32>)>,
+    gesture: Option<Gesture>,
+    keymap: Keymap,
+    show_keymap: bool,
+    /// The command waiting for the next key to be bound to it.
+    rebinding: Option<Command>,
+    /// Whether the mouse is over the canvas.
+    hovering: bool,
+    /// Whether shift is held, as of the last key or mouse event.
+    shift_held: bool,
+    drag: Option<Drag>,
+
+    // clipboard things
+    /// The last region copied, and the share code it was copied as.
+    clipboard: Option<(String, Floating)>,
+    pasting: bool,
 
     // canvas things
+    show_grid: bool,
+    /// Everything in the editor, for checking if it has focus.
+    container: NodeRef,
     canvas: NodeRef,
     canvas_size: Vector2<f32>,
     gl: Option<GL>,
     basic_shader: Option<CanvasShader>,
-    panel_textures: PanelMap<Option<AsyncTexture>>,
+    panel_images: PanelMap<Option<HtmlImageElement>>,
+    panel_atlas: Option<PanelAtlas>,
+    /// Every panel and exit on the field, ready to be drawn in one go.
+    panel_batch: Option<SpriteBatch>,
+    /// The field `panel_batch` was built from.
+    batched_field: Weak<Field>,
+
+    /// Whether to render every frame, for anything animated.
+    ///
+    /// Otherwise, frames are only rendered when something asks for one with
+    /// [`FieldEditor::request_render`].
+    continuous: bool,
 
     // callback things
+    /// The next frame to render, if one has been asked for.
     _render_request: Option<RenderTask>,
+    /// Listens for each panel image to finish loading, until the atlas is
+    /// packed.
+    _image_listeners: Vec<EventListener>,
     _resize_request: Option<ResizeTask>,
+    _keydown_listener: Option<KeyListenerHandle>,
+    _keyup_listener: Option<KeyListenerHandle>,
+    _context_lost_listener: Option<EventListener>,
+    _context_restored_listener: Option<EventListener>,
+    _long_press: Option<TimeoutTask>,
 }
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct Props {
     pub view: EditorView,
     pub onupdate: Callback<EditorView>,
+    pub onimport: Callback<File>,
+    pub onexport: Callback<()>,
 }
 
 pub enum Msg {
     Render(f64),
-    MouseMove(web_sys::MouseEvent),
-    MouseUp(web_sys::MouseEvent),
+    PointerDown(web_sys::PointerEvent),
+    PointerMove(web_sys::PointerEvent),
+    PointerUp(web_sys::PointerEvent),
+    PointerCancel(web_sys::PointerEvent),
+    PointerLeave,
+    LongPress,
     MouseWheel(web_sys::WheelEvent),
     ContextMenu(web_sys::MouseEvent),
+    KeyDown(web_sys::KeyboardEvent),
+    KeyUp(web_sys::KeyboardEvent),
+    ContextLost,
+    ContextRestored,
+    DragOver(web_sys::DragEvent),
+    Drop(web_sys::DragEvent),
     PanelKindSelect(PanelKind),
+    ToolSelect(Tool),
+    ToggleFillEmpty,
+    ToggleGrid,
+    ToggleKeymap,
+    Rebind(Command),
+    ResetKeymap,
+    FillSelection,
+    DeleteSelection,
+    AutoRoute(bool),
+    ExportImage,
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    /// Pastes a share code from the system clipboard, if it could be read.
+    PasteCode(Option<String>),
+    Cancel,
     Resize,
+    /// A panel image has loaded, or failed to.
+    ImageLoaded,
+}
+
+/// What a held left mouse button is doing.
+#[derive(Clone, Copy)]
+enum Drag {
+    /// Painting a brush stroke.
+    Paint(Brush),
+    /// Selecting a rectangle, from the cell the drag started on.
+    Select((isize, isize)),
+    /// Moving lifted panels, from the cell the drag started on and the last
+    /// cell the mouse was on.
+    Move {
+        start: (isize, isize),
+        last: (isize, isize),
+    },
+}
+
+/// What fingers on the canvas are doing.
+#[derive(Clone)]
+enum Gesture {
+    /// One finger is down, but hasn't moved or been held long enough to do
+    /// anything yet.
+    Pending(MouseEvent),
+    /// One finger is dragging, like a held left mouse button.
+    Drag,
+    /// Two fingers are panning and zooming.
+    Pinch { center: Vector2<f32>, distance: f32 },
 }
 
 impl Component for FieldEditor {
@@ -62,82 +173,151 @@ impl Component for FieldEditor {
             link,
             props,
             mouse_last: MouseEvent::default(),
+            touches: Vec::new(),
+            gesture: None,
+            keymap: Keymap::load(),
+            show_keymap: false,
+            rebinding: None,
+            hovering: false,
+            shift_held: false,
+            drag: None,
+            clipboard: None,
+            pasting: false,
+            show_grid: true,
+            container: NodeRef::default(),
             canvas: NodeRef::default(),
             canvas_size: na::zero(),
             gl: None,
             basic_shader: None,
-            panel_textures: PanelMap::new_empty(),
+            panel_images: PanelMap::new_empty(),
+            panel_atlas: None,
+            panel_batch: None,
+            batched_field: Weak::new(),
+            continuous: false,
             _render_request: None,
+            _image_listeners: Vec::new(),
             _resize_request: None,
+            _keydown_listener: None,
+            _keyup_listener: None,
+            _context_lost_listener: None,
+            _context_restored_listener: None,
+            _long_press: None,
         }
     }
 
     fn rendered(&mut self, first_render: bool) {
-        // rebuild gl if gl is invalidated
-        if self.gl_invalidated() {
+        // after this, gl is only rebuilt when a lost context is restored
+        if first_render {
             self.build_gl();
-            self.build_basic_shader();
+            self.build_gl_resources();
 
-            if first_render {
-                // request for textures
-                self.request_panel_images();
-            }
+            // request for textures
+            self.request_panel_images();
         }
 
         self.update_size();
-
-        // only center if a center was requested
-        if self.props.view.needs_center {
-            self.props.view.center(&self.canvas_size);
-            // lower flag after centering is done
-            self.props.view.needs_center = false;
-        }
+        self.center_if_needed();
 
         if first_render {
             self.setup_callbacks();
         }
+
+        // the canvas may have been resized
+        self.request_render();
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        if changes_canvas(&msg) {
+            self.request_render();
+        }
+
         match msg {
             Msg::Render(timestamp) => {
-                // render the field editor
+                self._render_request = None;
+
+                // render the field editor, once there is something to
+                // render it with
                 if self.textures_loaded() {
                     self.render(timestamp);
                 }
 
-                // setup another request
-                self.request_animation_frame();
+                if self.continuous {
+                    self.request_render();
+                }
             }
-            Msg::MouseMove(ev) => {
-                let ev: MouseEvent = (&ev).into();
+            Msg::PointerDown(ev) => {
+                // keep getting events if the pointer leaves the canvas, so
+                // drags always see the button come back up
+                let _ = self.canvas().set_pointer_capture(ev.pointer_id());
+
+                if is_touch(&ev) {
+                    self.touch_down(&ev);
+                } else {
+                    let ev: MouseEvent = (&*ev).into();
+
+                    if ev.button().left() {
+                        self.press_left(&ev);
+                    }
+
+                    self.mouse_last = ev;
+                }
+            }
+            Msg::PointerMove(ev) if is_touch(&ev) => self.touch_move(&ev),
+            Msg::PointerMove(ev) => {
+                let ev: MouseEvent = (&*ev).into();
+
+                // pressing a button while another is held only shows up as
+                // a move
+                let pressed = ev.button().left() && ev.buttons().left();
+
+                if pressed {
+                    self.press_left(&ev);
+                }
 
                 // handle mouse move if mouse is down
                 if ev.buttons().right() {
                     self.props.view.pan(ev.pos() - self.mouse_last.pos());
                 }
 
+                if self.pasting {
+                    self.move_paste(&ev);
+                }
+
+                // a drag that only just started has nothing to continue
+                if let Some(drag) = self.drag.filter(|_| !pressed) {
+                    if ev.buttons().left() {
+                        self.drag = Some(self.continue_drag(drag, &ev));
+                    } else {
+                        // the button was released somewhere we couldn't see
+                        self.end_drag();
+                    }
+                }
+
                 // set as last mouse event
+                self.shift_held = ev.modifiers().shift();
                 self.mouse_last = ev;
+                self.hovering = true;
             }
-            Msg::MouseUp(ev) => {
-                let ev: MouseEvent = (&ev).into();
+            Msg::PointerUp(ev) if is_touch(&ev) => self.touch_up(&ev, false),
+            Msg::PointerUp(ev) => {
+                let ev: MouseEvent = (&*ev).into();
 
-                // handle panel placement if mouse is down
                 if ev.button().left() {
-                    if ev.modifiers().shift() {
-                        // delete tile
-                        *self.props.view.flex_mut(&ev.pos()) = Panel::EMPTY;
-                        self.props.view.collapse();
-                    } else {
-                        // place current tile
-                        self.props.view.flex_mut(&ev.pos()).kind = self.props.view.selected;
-                        self.props.view.collapse();
-                    }
+                    self.end_drag();
+                }
+            }
+            Msg::PointerCancel(ev) if is_touch(&ev) => self.touch_up(&ev, true),
+            Msg::PointerCancel(_) => self.end_drag(),
+            Msg::PointerLeave => {
+                self.hovering = false;
+            }
+            Msg::LongPress => {
+                self._long_press = None;
 
-                    // we're lazy; only call update if the field has been
-                    // updated
-                    self.emit_update();
+                // holding a finger still erases, like shift-clicking
+                if let Some(Gesture::Pending(ev)) = self.gesture.take() {
+                    self.drag = self.begin_drag(&ev, true);
+                    self.gesture = Some(Gesture::Drag);
                 }
             }
             Msg::MouseWheel(ev) => {
@@ -146,28 +326,171 @@ impl Component for FieldEditor {
                 let delta = ev.delta_y() * -0.01;
 
                 // handle scroll? ez
-                let scale = self.props.view.get_scale();
-                // cap scroll
-                if delta > 0. {
-                    if scale.x.max(scale.y) < EditorView::MAX_ZOOM {
-                        self.props.view.scale(1. + delta, ev.pos());
-                    }
-                } else {
-                    if scale.x.max(scale.y) > EditorView::MIN_ZOOM {
-                        self.props.view.scale(1. + delta, ev.pos());
-                    }
-                }
+                self.zoom(1. + delta, ev.pos());
             }
             Msg::ContextMenu(ev) => {
                 ev.prevent_default();
             }
+            Msg::DragOver(ev) => {
+                // allows the canvas to be dropped on
+                ev.prevent_default();
+            }
+            Msg::Drop(ev) => {
+                ev.prevent_default();
+
+                let file = ev
+                    .data_transfer()
+                    .and_then(|data| data.files())
+                    .and_then(|files| files.get(0));
+
+                if let Some(file) = file {
+                    self.props.onimport.emit(file);
+                }
+            }
+            Msg::KeyDown(ev) => {
+                // shift changes what a click would do
+                self.shift_held = ev.shift_key();
+
+                // keys typed anywhere else on the page aren't for the editor
+                if !self.has_focus() || is_typing(&ev) {
+                    return false;
+                }
+
+                if let Some(command) = self.rebinding {
+                    return self.rebind(command, &ev);
+                }
+
+                let command = match self.keymap.get(&Binding::from_event(&ev)) {
+                    Some(command) if self.can_run(command) => command,
+                    _ => return false,
+                };
+
+                // don't let the browser do anything of its own
+                ev.prevent_default();
+
+                return self.run_command(command);
+            }
+            Msg::KeyUp(ev) => {
+                self.shift_held = ev.shift_key();
+            }
+            Msg::ContextLost => {
+                // everything made with the context is dead now
+                self.drop_gl_resources();
+                self.gl = None;
+            }
+            Msg::ContextRestored => {
+                self.build_gl();
+                self.build_gl_resources();
+                self.update_size();
+            }
             Msg::PanelKindSelect(kind) => {
                 self.props.view.selected = kind;
+
+                // update the panel selector, in case this came from a key
+                return true;
+            }
+            Msg::ToolSelect(tool) => {
+                self.props.view.tool = tool;
+
+                // update the tool bar
+                return true;
+            }
+            Msg::ToggleFillEmpty => {
+                self.props.view.fill_empty = !self.props.view.fill_empty;
+
+                // update the tool bar
+                return true;
+            }
+            Msg::ToggleGrid => {
+                self.show_grid = !self.show_grid;
+
+                // update the tool bar
+                return true;
+            }
+            Msg::ToggleKeymap => {
+                self.show_keymap = !self.show_keymap;
+                self.rebinding = None;
+
+                return true;
+            }
+            Msg::Rebind(command) => {
+                self.rebinding = Some(command);
+
+                return true;
+            }
+            Msg::ResetKeymap => {
+                self.keymap = Keymap::default();
+                self.keymap.save();
+                self.rebinding = None;
+
+                return true;
+            }
+            Msg::ExportImage => self.export_image(),
+            Msg::FillSelection => {
+                let brush = Brush::Place(self.props.view.selected);
+
+                self.edit_selection(brush);
+            }
+            Msg::DeleteSelection => {
+                self.edit_selection(Brush::Erase);
+            }
+            Msg::AutoRoute(force) => {
+                let routes = self.props.view.auto_route(force);
+
+                if !routes.is_empty() {
+                    self.props.view.checkpoint();
+                    self.props.view.set_exits(routes);
+
+                    self.emit_update();
+                }
+            }
+            Msg::Undo => {
+                if self.props.view.undo() {
+                    self.emit_update();
+                }
+            }
+            Msg::Redo => {
+                if self.props.view.redo() {
+                    self.emit_update();
+                }
+            }
+            Msg::Copy => self.copy_selection(),
+            Msg::Cut => {
+                self.copy_selection();
+                self.edit_selection(Brush::Erase);
+            }
+            Msg::Paste => {
+                let link = self.link.clone();
+
+                // prefer the system clipboard, so regions can come from
+                // other tabs
+                spawn_local(async move {
+                    let code = util::read_clipboard().await.ok();
+
+                    link.send_message(Msg::PasteCode(code));
+                });
+            }
+            Msg::PasteCode(code) => {
+                if let Some(region) = self.pasted_region(code) {
+                    self.begin_paste(region);
+                }
+            }
+            Msg::Cancel => {
+                if self.pasting {
+                    self.pasting = false;
+                    self.props.view.floating = None;
+                }
+
+                self.props.view.selection = None;
             }
             Msg::Resize => {
                 // rerender
                 return true;
             }
+            Msg::ImageLoaded => {
+                // the render this asks for packs the atlas if it was the last
+                // image
+            }
         };
 
         false
@@ -175,25 +498,33 @@ impl Component for FieldEditor {
 
     fn view(&self) -> Html {
         html! {
-            <div class="editor-container">
+            <div class="editor-container" ref=self.container.clone()>
                 <panel::PanelSelector onselect=self.link.callback(Msg::PanelKindSelect)
                                       selected=self.props.view.selected />
-                <canvas class="editor-canvas"
-                        oncontextmenu=self.link.callback(Msg::ContextMenu)
-                        onmousemove=self.link.callback(Msg::MouseMove)
-                        onmouseup=self.link.callback(Msg::MouseUp)
-                        onwheel=self.link.callback(Msg::MouseWheel)
-                        ref=self.canvas.clone()>
-                </canvas>
+                { self.view_tool_bar() }
+                { self.view_keymap() }
+                { self.view_canvas() }
             </div>
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if self.props != props {
-            self.props = props;
+        // only take the view if it's actually a different field; otherwise
+        // we would throw away any panning done since the last update
+        if !Rc::ptr_eq(&self.props.view.field, &props.view.field) {
+            self.props.view = props.view;
+            self.request_render();
+        } else if props.view.needs_focus.is_some() {
+            self.props.view.needs_focus = props.view.needs_focus;
+            self.request_render();
         }
 
+        self.center_if_needed();
+
+        self.props.onupdate = props.onupdate;
+        self.props.onimport = props.onimport;
+        self.props.onexport = props.onexport;
+
         // the editor is managed by the GL, so we don't need to re-render
         false
     }
@@ -202,28 +533,380 @@ impl Component for FieldEditor {
 impl FieldEditor {
     /// Renders the field editor to the attached canvas.
     pub fn render(&mut self, _timestamp: f64) {
-        let basic = match self.basic_shader.as_mut() {
-            Some(basic) => basic,
+        let hover = self
+            .hover_brush()
+            .map(|brush| (self.props.view.pos(&self.mouse_last.pos()), brush));
+
+        self.update_panel_batch();
+
+        let (basic, atlas) = match (self.basic_shader.as_mut(), self.panel_atlas.as_ref()) {
+            (Some(basic), Some(atlas)) => (basic, atlas),
+            _ => return,
+        };
+
+        let scene = Scene {
+            view: &self.props.view,
+            atlas,
+            panels: self.panel_batch.as_ref(),
+            canvas_size: self.canvas_size,
+            hover,
+            pasting: self.pasting,
+            show_grid: self.show_grid,
+        };
+
+        scene.draw(&mut basic.begin_draw());
+    }
+
+    /// How far the arrow keys pan the view, in pixels.
+    const PAN_STEP: f32 = 64.;
+    /// How much the zoom keys scale the view.
+    const ZOOM_STEP: f32 = 1.25;
+
+    /// Checks if a key should run a command, instead of doing whatever the
+    /// browser would do with it.
+    ///
+    /// The editor should already have focus.
+    fn can_run(&self, command: Command) -> bool {
+        match command {
+            // let the browser have these if there's nothing to act on; the
+            // system clipboard can only be read once the key is handled, so
+            // paste always goes through
+            Command::Copy | Command::Cut | Command::Delete => self.props.view.selection.is_some(),
+            _ => true,
+        }
+    }
+
+    /// Binds the key pressed to a command, or stops rebinding if it was
+    /// escape.
+    fn rebind(&mut self, command: Command, ev: &web_sys::KeyboardEvent) -> ShouldRender {
+        let binding = Binding::from_event(ev);
+
+        // wait for the key the modifiers go with
+        if binding.is_modifier() {
+            return false;
+        }
+
+        ev.prevent_default();
+
+        if binding != Binding::new("Escape") {
+            self.keymap.rebind(command, binding);
+            self.keymap.save();
+        }
+
+        self.rebinding = None;
+        true
+    }
+
+    /// Checks if anything in the editor has focus.
+    fn has_focus(&self) -> bool {
+        let document = web_sys::window().unwrap().document().unwrap();
+
+        match (document.active_element(), self.container.get()) {
+            (Some(active), Some(container)) => container.contains(Some(&active)),
+            _ => false,
+        }
+    }
+
+    fn run_command(&mut self, command: Command) -> ShouldRender {
+        let msg = match command {
+            Command::SelectPanel(index) => match assets::selectable_panel(index) {
+                Some(kind) => Msg::PanelKindSelect(kind),
+                None => return false,
+            },
+            Command::SelectTool(tool) => Msg::ToolSelect(tool),
+            Command::Pan(direction) => {
+                // move the view, not the field
+                let (x, y) = direction.offset();
+                let pan = Vector2::new(x as f32, y as f32) * -Self::PAN_STEP;
+
+                self.props.view.pan(pan);
+                return false;
+            }
+            Command::ZoomIn => {
+                self.zoom(Self::ZOOM_STEP, self.canvas_size / 2.);
+                return false;
+            }
+            Command::ZoomOut => {
+                self.zoom(1. / Self::ZOOM_STEP, self.canvas_size / 2.);
+                return false;
+            }
+            Command::Undo => Msg::Undo,
+            Command::Redo => Msg::Redo,
+            Command::Copy => Msg::Copy,
+            Command::Cut => Msg::Cut,
+            Command::Paste => Msg::Paste,
+            Command::Delete => Msg::DeleteSelection,
+            Command::Cancel => Msg::Cancel,
+            Command::Save => {
+                self.props.onexport.emit(());
+                return false;
+            }
+        };
+
+        self.update(msg)
+    }
+
+    /// Scales the view by a point, without going past the zoom limits.
+    fn zoom(&mut self, factor: f32, at: Vector2<f32>) {
+        let scale = self.props.view.get_scale();
+
+        // cap scroll
+        if factor > 1. {
+            if scale.x.max(scale.y) < EditorView::MAX_ZOOM {
+                self.props.view.scale(factor, at);
+            }
+        } else {
+            if scale.x.max(scale.y) > EditorView::MIN_ZOOM {
+                self.props.view.scale(factor, at);
+            }
+        }
+    }
+
+    /// The default size of a cell in exported images, in pixels.
+    const IMAGE_CELL_SIZE: u32 = 64;
+
+    fn export_image(&mut self) {
+        let cell_size =
+            DialogService::prompt("Pixels per cell:", Some(&Self::IMAGE_CELL_SIZE.to_string()));
+
+        let cell_size = match cell_size.map(|size| size.trim().parse::<u32>()) {
+            Some(Ok(size)) if size > 0 => size,
+            Some(_) => {
+                DialogService::alert("That isn't a valid size.");
+                return;
+            }
             None => return,
         };
 
-        // clear
+        let result = self
+            .render_image(cell_size)
+            .and_then(|png| util::download("field.png", &png));
+
+        if let Err(err) = result {
+            DialogService::alert(&format!(
+                "Could not save image: {}",
+                err.as_string().unwrap_or_default()
+            ));
+        }
+    }
+
+    /// Renders the whole field to a PNG image, no matter where the view is.
+    fn render_image(&mut self, cell_size: u32) -> Result<Vec<u8>, JsValue> {
+        if !self.textures_loaded() {
+            return Err(JsValue::from_str("the panel images haven't loaded yet"));
+        }
+
+        self.update_panel_batch();
+
+        let (gl, basic) = match (self.gl.as_ref(), self.basic_shader.as_mut()) {
+            (Some(gl), Some(basic)) => (gl, basic),
+            _ => return Err(JsValue::from_str("WebGL is not available")),
+        };
+
+        let atlas = self
+            .panel_atlas
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("the panel images haven't loaded yet"))?;
+
+        let field = &self.props.view.field;
+        let width = (field.width() as u32).saturating_mul(cell_size);
+        let height = (field.height() as u32).saturating_mul(cell_size);
+
+        if width == 0 || height == 0 {
+            return Err(JsValue::from_str("the field is empty"));
+        }
+
+        let max_size = gl.max_render_target_size();
+
+        if width > max_size || height > max_size {
+            return Err(JsValue::from_str(&format!(
+                "the image would be too big; it can be at most {} pixels on either side",
+                max_size
+            )));
+        }
+
+        let target = gl
+            .render_target(width, height)
+            .ok_or_else(|| JsValue::from_str("could not create a framebuffer"))?;
+
+        gl.viewport(0, 0, width as i32, height as i32);
+        basic.rebuild_projection(&Vector2::new(width as f32, height as f32));
         basic.clear();
 
-        // setup view matrix
-        let mut draw = basic.begin_draw();
-        draw.set_transform(&self.props.view.view);
+        {
+            let mut draw = basic.begin_draw();
+            draw.set_transform(&Matrix4::new_scaling(cell_size as f32));
+
+            if let Some(batch) = &self.panel_batch {
+                draw.texture(atlas.texture());
+                draw.draw_batch(batch);
+            }
+        }
+
+        let pixels = target.read_pixels();
+        drop(target);
+
+        // go back to drawing to the canvas
+        gl.viewport(0, 0, self.canvas_size.x as i32, self.canvas_size.y as i32);
+        basic.rebuild_projection(&self.canvas_size);
+
+        // GL reads from the bottom up, but images go from the top down
+        let row = (width * 4) as usize;
+        let pixels: Vec<u8> = pixels.chunks(row).rev().flatten().copied().collect();
+
+        util::encode_png(width, height, &pixels)
+    }
+
+    /// Rebuilds the panel batch if the field has changed since it was last
+    /// built.
+    ///
+    /// The batch holds the whole field, not just what is on the canvas.
+    /// Culling it to the view would mean rebuilding it every time the view
+    /// pans, where drawing panels off of the canvas only costs the GPU some
+    /// vertices it throws away.
+    fn update_panel_batch(&mut self) {
+        let (gl, atlas) = match (self.gl.as_ref(), self.panel_atlas.as_ref()) {
+            (Some(gl), Some(atlas)) => (gl, atlas),
+            _ => return,
+        };
+
+        // every change to the field makes a new one, so the pointer is
+        // enough to tell
+        let field = &self.props.view.field;
+
+        if self.panel_batch.is_some() && self.batched_field.ptr_eq(&Rc::downgrade(field)) {
+            return;
+        }
+
+        self.panel_batch
+            .get_or_insert_with(|| SpriteBatch::new(gl))
+            .set(gl, scene::field_rects(atlas, field, na::zero()));
+        self.batched_field = Rc::downgrade(field);
+    }
+
+    /// Gets what would be painted at the mouse, if anything.
+    fn hover_brush(&self) -> Option<Brush> {
+        if !self.hovering || self.pasting || self.mouse_last.buttons().right() {
+            return None;
+        }
+
+        match self.props.view.tool {
+            Tool::Brush | Tool::Bucket if self.shift_held => Some(Brush::Erase),
+            Tool::Brush | Tool::Bucket => Some(Brush::Place(self.props.view.selected)),
+            Tool::Select | Tool::Exits => None,
+        }
+    }
+
+    fn view_canvas(&self) -> Html {
+        html! {
+            <canvas class="editor-canvas"
+                    tabindex="0"
+                    oncontextmenu=self.link.callback(Msg::ContextMenu)
+                    ondragover=self.link.callback(Msg::DragOver)
+                    ondrop=self.link.callback(Msg::Drop)
+                    onpointerdown=self.link.callback(Msg::PointerDown)
+                    onpointermove=self.link.callback(Msg::PointerMove)
+                    onpointerup=self.link.callback(Msg::PointerUp)
+                    onpointercancel=self.link.callback(Msg::PointerCancel)
+                    onpointerleave=self.link.callback(|_| Msg::PointerLeave)
+                    onwheel=self.link.callback(Msg::MouseWheel)
+                    ref=self.canvas.clone()>
+            </canvas>
+        }
+    }
+
+    fn view_keymap(&self) -> Html {
+        if !self.show_keymap {
+            return html! {};
+        }
 
-        for (x, y) in self.props.view.field.iter() {
-            let panel = self.props.view.field.get(x, y);
-            let (x, y) = (x as f32, y as f32);
+        html! {
+            <KeymapPanel keymap=self.keymap.clone()
+                         rebinding=self.rebinding
+                         onrebind=self.link.callback(Msg::Rebind)
+                         onreset=self.link.callback(|_| Msg::ResetKeymap)
+                         onclose=self.link.callback(|_| Msg::ToggleKeymap) />
+        }
+    }
 
-            if let Some(tex) = &self.panel_textures[panel.kind] {
-                let tex = tex.unwrap();
+    fn view_tool_bar(&self) -> Html {
+        let tool_button = |tool: Tool, name: &str| {
+            let class = if tool == self.props.view.tool {
+                "tool-button selected"
+            } else {
+                "tool-button"
+            };
+
+            html! {
+                <a class=class
+                   href="#"
+                   onclick=util::link_callback(&self.link, move |_| Msg::ToolSelect(tool))>
+                    { name }
+                </a>
+            }
+        };
+
+        let toggle_button = |checked: bool, msg: fn() -> Msg, name: &str| {
+            html! {
+                <label class="tool-button">
+                    <input type="checkbox"
+                           checked=checked
+                           onclick=self.link.callback(move |_| msg()) />
+                    { name }
+                </label>
+            }
+        };
 
-                draw.texture(tex);
-                draw.draw_full_rect(Rect::UNIT.offset(Vector2::new(x, y)));
+        let action_button = |msg: fn() -> Msg, name: &str| {
+            html! {
+                <a class="tool-button"
+                   href="#"
+                   onclick=util::link_callback(&self.link, move |_| msg())>
+                    { name }
+                </a>
             }
+        };
+
+        let tools = html! {
+            <>
+                { tool_button(Tool::Brush, "Brush") }
+                { tool_button(Tool::Select, "Select") }
+                { tool_button(Tool::Bucket, "Bucket") }
+                { tool_button(Tool::Exits, "Exits") }
+                { toggle_button(self.props.view.fill_empty, || Msg::ToggleFillEmpty, "Fill empty") }
+                { toggle_button(self.show_grid, || Msg::ToggleGrid, "Grid") }
+            </>
+        };
+
+        let selection_actions = html! {
+            <>
+                { action_button(|| Msg::FillSelection, "Fill") }
+                { action_button(|| Msg::DeleteSelection, "Delete") }
+                <a class="tool-button"
+                   href="#"
+                   title="Shift-click to also reroute panels set by hand"
+                   onclick=util::link_callback(&self.link, |ev: web_sys::MouseEvent| Msg::AutoRoute(ev.shift_key()))>
+                    { "Auto-route" }
+                </a>
+            </>
+        };
+
+        let actions = html! {
+            <>
+                { action_button(|| Msg::ExportImage, "Save image...") }
+                { action_button(|| Msg::Copy, "Copy") }
+                { action_button(|| Msg::Cut, "Cut") }
+                { action_button(|| Msg::Paste, "Paste") }
+                { action_button(|| Msg::ToggleKeymap, "Keys...") }
+            </>
+        };
+
+        html! {
+            <div class="tool-bar">
+                { tools }
+                { selection_actions }
+                { actions }
+            </div>
         }
     }
 
@@ -235,11 +918,362 @@ impl FieldEditor {
         self.props.onupdate.emit(self.props.view.clone())
     }
 
-    fn gl_invalidated(&self) -> bool {
-        self.gl
-            .as_ref()
-            .map(|gl| gl.is_context_lost())
-            .unwrap_or(true)
+    /// How far a finger can move before it stops counting as a tap, in
+    /// pixels.
+    const TAP_SLOP: f32 = 8.;
+    /// How long a finger has to be held still to erase.
+    const LONG_PRESS: Duration = Duration::from_millis(500);
+
+    fn touch_down(&mut self, ev: &web_sys::PointerEvent) {
+        let mouse: MouseEvent = (&**ev).into();
+
+        self.touches.push((ev.pointer_id(), mouse.pos()));
+        // fingers don't hover
+        self.hovering = false;
+
+        match self.touches.len() {
+            1 => {
+                let long_press = self.link.callback(|_| Msg::LongPress);
+                let handle = TimeoutService::spawn(Self::LONG_PRESS, long_press);
+
+                self._long_press = Some(handle);
+                self.gesture = Some(Gesture::Pending(mouse));
+            }
+            2 => {
+                // a second finger stops whatever the first was doing
+                self._long_press = None;
+
+                if let Some(Gesture::Drag) = self.gesture {
+                    self.end_drag();
+                }
+
+                let (center, distance) = self.pinch();
+                self.gesture = Some(Gesture::Pinch { center, distance });
+            }
+            _ => (),
+        }
+    }
+
+    fn touch_move(&mut self, ev: &web_sys::PointerEvent) {
+        let mouse: MouseEvent = (&**ev).into();
+
+        let id = ev.pointer_id();
+
+        match self.touches.iter_mut().find(|(touch, _)| *touch == id) {
+            Some(touch) => touch.1 = mouse.pos(),
+            None => return,
+        }
+
+        match self.gesture.clone() {
+            Some(Gesture::Pending(start))
+                if (mouse.pos() - start.pos()).norm() > Self::TAP_SLOP =>
+            {
+                // the finger moved, so this is a drag
+                self._long_press = None;
+
+                self.drag = self.begin_drag(&start, false);
+                self.mouse_last = start;
+                self.gesture = Some(Gesture::Drag);
+
+                self.touch_move(ev);
+            }
+            Some(Gesture::Drag) => {
+                if self.pasting {
+                    self.move_paste(&mouse);
+                }
+
+                if let Some(drag) = self.drag {
+                    self.drag = Some(self.continue_drag(drag, &mouse));
+                }
+
+                self.mouse_last = mouse;
+            }
+            Some(Gesture::Pinch { center, distance }) => {
+                let (new_center, new_distance) = self.pinch();
+
+                self.props.view.pan(new_center - center);
+
+                if distance > 0. {
+                    self.zoom(new_distance / distance, new_center);
+                }
+
+                self.gesture = Some(Gesture::Pinch {
+                    center: new_center,
+                    distance: new_distance,
+                });
+            }
+            Some(Gesture::Pending(_)) | None => (),
+        }
+    }
+
+    /// Lifts a finger. If `cancelled` is set, the browser took the touch
+    /// away, so a tap doesn't count.
+    fn touch_up(&mut self, ev: &web_sys::PointerEvent, cancelled: bool) {
+        self.touches.retain(|(id, _)| *id != ev.pointer_id());
+        self._long_press = None;
+
+        match self.gesture.take() {
+            Some(Gesture::Pending(start)) if !cancelled => {
+                // a tap is a click that doesn't go anywhere
+                if self.pasting {
+                    self.move_paste(&start);
+                    self.end_paste();
+                } else {
+                    self.drag = self.begin_drag(&start, false);
+                    self.end_drag();
+                }
+            }
+            Some(Gesture::Drag) => self.end_drag(),
+            // keep pinching until every finger is gone, so the last finger
+            // doesn't start painting
+            Some(Gesture::Pinch { .. }) if self.touches.len() >= 2 => {
+                let (center, distance) = self.pinch();
+                self.gesture = Some(Gesture::Pinch { center, distance });
+            }
+            _ => (),
+        }
+    }
+
+    /// Gets the center of the first two fingers, and the distance between
+    /// them.
+    fn pinch(&self) -> (Vector2<f32>, f32) {
+        match self.touches.as_slice() {
+            [(_, a), (_, b), ..] => ((a + b) / 2., (a - b).norm()),
+            [(_, a)] => (*a, 0.),
+            [] => (Vector2::zeros(), 0.),
+        }
+    }
+
+    /// Handles the left mouse button being pressed.
+    fn press_left(&mut self, ev: &MouseEvent) {
+        if self.pasting {
+            self.end_paste();
+        } else {
+            let erase = ev.modifiers().shift();
+            self.drag = self.begin_drag(ev, erase);
+        }
+    }
+
+    fn begin_drag(&mut self, ev: &MouseEvent, erase: bool) -> Option<Drag> {
+        let cell = self.props.view.pos(&ev.pos());
+
+        match self.props.view.tool {
+            Tool::Brush => {
+                let brush = if erase {
+                    // delete tiles
+                    Brush::Erase
+                } else {
+                    // place current tile
+                    Brush::Place(self.props.view.selected)
+                };
+
+                self.props.view.checkpoint();
+                self.props.view.paint_line(&ev.pos(), &ev.pos(), brush);
+
+                Some(Drag::Paint(brush))
+            }
+            Tool::Select => {
+                let grabbed = self
+                    .props
+                    .view
+                    .selection
+                    .map(|selection| selection.contains(cell))
+                    .unwrap_or(false);
+
+                if grabbed {
+                    self.props.view.checkpoint();
+                    self.props.view.lift_selection();
+
+                    Some(Drag::Move {
+                        start: cell,
+                        last: cell,
+                    })
+                } else {
+                    self.props.view.selection = Some(Selection::from_corners(cell, cell));
+
+                    Some(Drag::Select(cell))
+                }
+            }
+            Tool::Bucket => {
+                // erasing fills with nothing, and never spreads into empty
+                // cells
+                let (brush, kind, fill_empty) = if erase {
+                    (Brush::Erase, PanelKind::Empty, false)
+                } else {
+                    let kind = self.props.view.selected;
+                    (Brush::Place(kind), kind, self.props.view.fill_empty)
+                };
+
+                let area = self.props.view.fill_area(cell, kind, fill_empty);
+
+                if !area.is_empty() {
+                    self.props.view.checkpoint();
+                    self.props.view.paint_cells(area, brush);
+                    self.props.view.collapse();
+
+                    self.emit_update();
+                }
+
+                None
+            }
+            Tool::Exits => {
+                if let Some((cell, direction)) = self.props.view.edge(&ev.pos()) {
+                    self.props.view.checkpoint();
+                    self.props.view.toggle_exit(cell, direction);
+
+                    self.emit_update();
+                }
+
+                None
+            }
+        }
+    }
+
+    fn continue_drag(&mut self, drag: Drag, ev: &MouseEvent) -> Drag {
+        let cell = self.props.view.pos(&ev.pos());
+
+        match drag {
+            Drag::Paint(brush) => {
+                self.props
+                    .view
+                    .paint_line(&self.mouse_last.pos(), &ev.pos(), brush);
+            }
+            Drag::Select(anchor) => {
+                self.props.view.selection = Some(Selection::from_corners(anchor, cell));
+            }
+            Drag::Move { start, last } => {
+                self.props
+                    .view
+                    .move_floating((cell.0 - last.0, cell.1 - last.1));
+
+                return Drag::Move { start, last: cell };
+            }
+        }
+
+        drag
+    }
+
+    /// Finishes a drag, treating it as a single update.
+    fn end_drag(&mut self) {
+        match self.drag.take() {
+            Some(Drag::Paint(_)) => (),
+            Some(Drag::Move { start, last }) if start == last => {
+                // the selection was only clicked, so nothing was moved
+                self.props.view.floating = None;
+                self.props.view.cancel_edit();
+                return;
+            }
+            Some(Drag::Move { .. }) => self.props.view.drop_floating(),
+            Some(Drag::Select(_)) | None => return,
+        }
+
+        self.props.view.collapse();
+
+        // a stroke over panels that were already right doesn't count
+        if self.props.view.end_edit() {
+            self.emit_update();
+        }
+    }
+
+    fn copy_selection(&mut self) {
+        if let Some(region) = self.props.view.copy_floating() {
+            let code = format::encode_base64(&region.panels, base64::STANDARD);
+            let text = code.clone();
+
+            spawn_local(async move {
+                // the internal clipboard is still there if this fails
+                let _ = util::write_clipboard(&text).await;
+            });
+
+            self.clipboard = Some((code, region));
+        }
+    }
+
+    /// Gets the region to paste, given what was in the system clipboard.
+    fn pasted_region(&self, code: Option<String>) -> Option<Floating> {
+        let copied = self.clipboard.as_ref();
+
+        // our own copy also knows which exits were set by hand
+        let is_copied = |code: &String| copied.map(|(copied, _)| copied == code.trim());
+
+        code.filter(|code| !is_copied(code).unwrap_or(false))
+            .and_then(|code| format::decode_base64(&code, base64::STANDARD).ok())
+            .map(Floating::new)
+            .or_else(|| copied.map(|(_, region)| region.clone()))
+    }
+
+    /// Starts previewing a paste under the cursor.
+    fn begin_paste(&mut self, region: Floating) {
+        let selection = Selection {
+            x: 0,
+            y: 0,
+            width: region.panels.width() as isize,
+            height: region.panels.height() as isize,
+        };
+
+        self.props.view.floating = Some(Floating {
+            pos: (0, 0),
+            ..region
+        });
+        self.props.view.selection = Some(selection);
+        self.pasting = true;
+
+        let ev = std::mem::take(&mut self.mouse_last);
+        self.move_paste(&ev);
+        self.mouse_last = ev;
+    }
+
+    fn move_paste(&mut self, ev: &MouseEvent) {
+        let cell = self.props.view.pos(&ev.pos());
+
+        if let Some(floating) = self.props.view.floating.as_ref() {
+            let offset = (cell.0 - floating.pos.0, cell.1 - floating.pos.1);
+
+            self.props.view.move_floating(offset);
+        }
+    }
+
+    /// Places the pasted panels onto the field.
+    fn end_paste(&mut self) {
+        self.pasting = false;
+
+        self.props.view.checkpoint();
+        self.props.view.drop_floating();
+        self.props.view.collapse();
+
+        if self.props.view.end_edit() {
+            self.emit_update();
+        }
+    }
+
+    fn edit_selection(&mut self, brush: Brush) {
+        if self.props.view.selection.is_some() {
+            self.props.view.checkpoint();
+            self.props.view.fill_selection(brush);
+            self.props.view.collapse();
+
+            if self.props.view.end_edit() {
+                self.emit_update();
+            }
+        }
+    }
+
+    fn center_if_needed(&mut self) {
+        // only center if a center was requested, and we know how big the
+        // canvas is
+        if self.canvas_size == Vector2::zeros() {
+            return;
+        }
+
+        if self.props.view.needs_center {
+            self.props.view.center(&self.canvas_size);
+            // lower flag after centering is done
+            self.props.view.needs_center = false;
+        }
+
+        if let Some(cell) = self.props.view.needs_focus.take() {
+            self.props.view.focus(cell, &self.canvas_size);
+        }
     }
 
     fn build_gl(&mut self) {
@@ -255,6 +1289,19 @@ impl FieldEditor {
         }
     }
 
+    /// Creates everything that is drawn with, except for the panel atlas,
+    /// which is packed once the panel images have loaded.
+    fn build_gl_resources(&mut self) {
+        self.build_basic_shader();
+    }
+
+    /// Drops everything made with the current context.
+    fn drop_gl_resources(&mut self) {
+        self.basic_shader = None;
+        self.panel_atlas = None;
+        self.panel_batch = None;
+    }
+
     fn build_basic_shader(&mut self) {
         let gl = match self.gl.as_ref() {
             Some(gl) => gl,
@@ -302,27 +1349,77 @@ impl FieldEditor {
     }
 
     fn request_panel_images(&mut self) {
-        let gl = self.gl.as_ref().unwrap();
-
-        for (kind, image) in self.panel_textures.iter_mut() {
+        for (kind, image) in self.panel_images.iter_mut() {
             let src = assets::panel_source(kind);
 
             // make request
             if let Some(src) = src {
-                *image = Some(gl.async_load_texture(src));
+                let img = HtmlImageElement::new().unwrap();
+
+                // a broken image counts as complete too, so an error has to
+                // render as well or the atlas would never be packed
+                for &event in ["load", "error"].iter() {
+                    let loaded = self.link.callback(|_| Msg::ImageLoaded);
+
+                    self._image_listeners
+                        .push(EventListener::new(&img, event, move |_| loaded.emit(())));
+                }
+
+                img.set_src(src);
+
+                *image = Some(img);
             }
         }
     }
 
+    /// Checks if every panel image has loaded, packing them into an atlas
+    /// the first time they have.
     fn textures_loaded(&mut self) -> bool {
-        self.panel_textures
-            .iter_mut()
-            .all(|(_, tex)| tex.as_mut().map(|x| x.loaded()).unwrap_or(true))
+        if self.panel_atlas.is_some() {
+            return true;
+        }
+
+        let complete = self
+            .panel_images
+            .iter()
+            .all(|(_, img)| img.as_ref().map(|x| x.complete()).unwrap_or(true));
+
+        if let (true, Some(gl)) = (complete, self.gl.as_ref()) {
+            self.panel_atlas = Some(PanelAtlas::new(gl, &self.panel_images));
+            self._image_listeners.clear();
+        }
+
+        self.panel_atlas.is_some()
     }
 
     fn setup_callbacks(&mut self) {
         self.request_resize_event();
-        self.request_animation_frame();
+        self.request_key_events();
+        self.request_context_events();
+        self.request_render();
+    }
+
+    /// Asks for the editor to be rendered on the next animation frame.
+    ///
+    /// Nothing is rendered until something asks, and asking more than once
+    /// before the frame still only renders once.
+    pub fn request_render(&mut self) {
+        if self._render_request.is_none() {
+            self.request_animation_frame();
+        }
+    }
+
+    /// Sets whether to render every frame, regardless of whether anything
+    /// asked for it.
+    ///
+    /// Animations should turn this on while they are running, and back off
+    /// when they are done.
+    pub fn set_continuous(&mut self, continuous: bool) {
+        self.continuous = continuous;
+
+        if continuous {
+            self.request_render();
+        }
     }
 
     fn request_resize_event(&mut self) {
@@ -332,6 +1429,37 @@ impl FieldEditor {
         self._resize_request = Some(handle);
     }
 
+    fn request_key_events(&mut self) {
+        let window = web_sys::window().unwrap();
+
+        let keydown = self.link.callback(Msg::KeyDown);
+        let handle = KeyboardService::register_key_down(&window, keydown);
+        self._keydown_listener = Some(handle);
+
+        let keyup = self.link.callback(Msg::KeyUp);
+        let handle = KeyboardService::register_key_up(&window, keyup);
+        self._keyup_listener = Some(handle);
+    }
+
+    fn request_context_events(&mut self) {
+        let canvas = self.canvas();
+
+        let lost = self.link.callback(|_| Msg::ContextLost);
+        let restored = self.link.callback(|_| Msg::ContextRestored);
+
+        self._context_lost_listener =
+            Some(EventListener::new(&canvas, "webglcontextlost", move |ev| {
+                // tells the browser we can handle the context coming back
+                ev.prevent_default();
+                lost.emit(());
+            }));
+        self._context_restored_listener = Some(EventListener::new(
+            &canvas,
+            "webglcontextrestored",
+            move |_| restored.emit(()),
+        ));
+    }
+
     fn request_animation_frame(&mut self) {
         let render_frame = self.link.callback(Msg::Render);
         let handle = RenderService::request_animation_frame(render_frame);
@@ -339,3 +1467,38 @@ impl FieldEditor {
         self._render_request = Some(handle);
     }
 }
+
+/// Checks if a message could change what is on the canvas.
+fn changes_canvas(msg: &Msg) -> bool {
+    // drag events fire constantly while something is held over the canvas,
+    // and the rest only change things through other messages
+    !matches!(
+        msg,
+        Msg::Render(_)
+            | Msg::ContextMenu(_)
+            | Msg::DragOver(_)
+            | Msg::Drop(_)
+            | Msg::Paste
+            | Msg::Copy
+            | Msg::ExportImage
+    )
+}
+
+/// Checks if a key is being typed into something, like a text box.
+fn is_typing(ev: &web_sys::KeyboardEvent) -> bool {
+    let target = ev
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok());
+
+    match target {
+        Some(target) => {
+            target.is_content_editable()
+                || matches!(target.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        }
+        None => false,
+    }
+}
+
+fn is_touch(ev: &web_sys::PointerEvent) -> bool {
+    ev.pointer_type() == "touch"
+}
@@ -4,6 +4,7 @@ use yew::prelude::*;
 use citrus_common::PanelKind;
 
 use super::assets::{self, PanelMap};
+use crate::util;
 
 /// Panel selector component.
 pub struct PanelSelector {
@@ -86,31 +87,35 @@ impl PanelSelector {
     }
 
     fn generate_buttons(&self) -> Html {
+        let buttons = self
+            .panel_images
+            .iter()
+            .filter_map(|(kind, r)| Some((assets::panel_source(kind)?, kind, r)))
+            .map(|(src, kind, r)| self.view_button(src, kind, r));
+
         html! {
             <div class="panel-selector" style=self.get_style() >
-                {
-                    for self.panel_images.iter()
-                        .filter_map(|(kind, r)| {
-                            Some((assets::panel_source(kind)?, kind, r))
-                        })
-                        .map(|(src, kind, r)| {
-                            html! {
-                                <a class={ if kind == self.props.selected {
-                                       "panel-button selected"
-                                   } else {
-                                       "panel-button"
-                                   } }
-                                   href="#"
-                                   onclick=self.link.callback(move |_| Msg::Select(kind))>
-                                    <img ref=r.clone() src=src />
-                                </a>
-                            }
-                        })
-                }
+                { for buttons }
             </div>
         }
     }
 
+    fn view_button(&self, src: &str, kind: PanelKind, r: &NodeRef) -> Html {
+        let class = if kind == self.props.selected {
+            "panel-button selected"
+        } else {
+            "panel-button"
+        };
+
+        html! {
+            <a class=class
+               href="#"
+               onclick=util::link_callback(&self.link, move |_| Msg::Select(kind))>
+                <img ref=r.clone() src=src />
+            </a>
+        }
+    }
+
     fn get_style(&self) -> String {
         format!(
             concat!(
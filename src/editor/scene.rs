@@ -0,0 +1,240 @@
+//! Drawing the field editor, with any [`Renderer`].
+
+use citrus_common::field::Field;
+use citrus_core::{Brush, Direction, EditorView, Exits};
+use na::Vector2;
+
+use super::assets::PanelAtlas;
+use crate::gl::{Color, Rect};
+use crate::render::Renderer;
+
+/// Everything that goes on the editor canvas.
+pub struct Scene<'a, T, B> {
+    pub view: &'a EditorView,
+    pub atlas: &'a PanelAtlas<T>,
+    /// The field's panels and exits out of the atlas, already batched.
+    ///
+    /// Without a batch, every panel and exit is drawn on its own.
+    pub panels: Option<&'a B>,
+    /// The size of the canvas, in pixels.
+    pub canvas_size: Vector2<f32>,
+    /// What a click would do, and on which cell.
+    pub hover: Option<((isize, isize), Brush)>,
+    /// Whether the floating panels are being pasted.
+    pub pasting: bool,
+    pub show_grid: bool,
+}
+
+impl<'a, T, B> Scene<'a, T, B> {
+    const SELECTION_FILL: Color = Color::rgba(0x3d, 0x8b, 0xff, 0x40);
+    const SELECTION_LINE: Color = Color::rgb(0x3d, 0x8b, 0xff);
+    /// The width of the selection outline, in pixels.
+    const SELECTION_LINE_WIDTH: f32 = 2.;
+    const GRID_LINE: Color = Color::rgba(0x00, 0x00, 0x00, 0x20);
+    /// The width of grid lines, in pixels.
+    const GRID_LINE_WIDTH: f32 = 1.;
+    const BOUNDS_LINE: Color = Color::rgba(0x00, 0x00, 0x00, 0x60);
+    /// The width of the field bounds outline, in pixels.
+    const BOUNDS_LINE_WIDTH: f32 = 2.;
+    const GHOST_TINT: Color = Color::rgba(u8::MAX, u8::MAX, u8::MAX, 0x80);
+    const ERASE_FILL: Color = Color::rgba(0xff, 0x30, 0x30, 0x60);
+    const ERASE_LINE: Color = Color::rgb(0xff, 0x30, 0x30);
+
+    /// Draws the whole scene.
+    ///
+    /// The field goes first, with everything else on top.
+    pub fn draw<R>(&self, draw: &mut R)
+    where
+        R: Renderer<'a, Texture = T, Batch = B>,
+    {
+        draw.clear();
+        draw.set_transform(&self.view.view);
+
+        match self.panels {
+            Some(batch) => {
+                draw.texture(self.atlas.texture());
+                draw.draw_batch(batch);
+            }
+            None => draw_field(draw, self.atlas, &self.view.field, na::zero()),
+        }
+
+        self.draw_overlay(draw);
+    }
+
+    /// Draws everything on top of the field.
+    fn draw_overlay<R>(&self, draw: &mut R)
+    where
+        R: Renderer<'a, Texture = T, Batch = B>,
+    {
+        // draw lifted panels on top
+        if let Some(floating) = &self.view.floating {
+            let offset = Vector2::new(floating.pos.0 as f32, floating.pos.1 as f32);
+
+            // pasted panels aren't on the field yet
+            if self.pasting {
+                draw.set_tint(&Self::GHOST_TINT);
+            }
+
+            draw_field(draw, self.atlas, &floating.panels, offset);
+
+            draw.set_tint(&Color::WHITE);
+        }
+
+        // keep lines the same width on screen
+        let scale = self.view.get_scale();
+        let line_size = |width: f32| Vector2::new(width / scale.x, width / scale.y);
+
+        // preview what a click would do
+        if let Some(((x, y), brush)) = self.hover {
+            let rect = Rect::UNIT.offset(Vector2::new(x as f32, y as f32));
+
+            match brush {
+                Brush::Place(kind) => {
+                    if let Some(src) = self.atlas.region(kind) {
+                        draw.set_tint(&Self::GHOST_TINT);
+                        draw.texture(self.atlas.texture());
+                        draw.draw_rect(rect, src);
+                        draw.set_tint(&Color::WHITE);
+                    }
+                }
+                Brush::Erase => {
+                    draw.fill_rect(rect, &Self::ERASE_FILL);
+
+                    for edge in rect.outline(line_size(Self::SELECTION_LINE_WIDTH)).iter() {
+                        draw.fill_rect(*edge, &Self::ERASE_LINE);
+                    }
+                }
+            }
+        }
+
+        if self.show_grid {
+            draw_grid(
+                draw,
+                self.view,
+                &self.canvas_size,
+                line_size(Self::GRID_LINE_WIDTH),
+                &Self::GRID_LINE,
+            );
+
+            let field = &self.view.field;
+            let bounds = Rect::new(0., 0., field.width() as f32, field.height() as f32);
+
+            for edge in bounds.outline(line_size(Self::BOUNDS_LINE_WIDTH)).iter() {
+                draw.fill_rect(*edge, &Self::BOUNDS_LINE);
+            }
+        }
+
+        if let Some(selection) = self.view.selection {
+            let rect = Rect::new(
+                selection.x as f32,
+                selection.y as f32,
+                selection.width as f32,
+                selection.height as f32,
+            );
+
+            draw.fill_rect(rect, &Self::SELECTION_FILL);
+
+            for edge in rect.outline(line_size(Self::SELECTION_LINE_WIDTH)).iter() {
+                draw.fill_rect(*edge, &Self::SELECTION_LINE);
+            }
+        }
+    }
+}
+
+/// Draws every panel in a field that has an image, and its exits.
+pub fn draw_field<'t, R>(
+    draw: &mut R,
+    atlas: &'t PanelAtlas<R::Texture>,
+    field: &Field,
+    offset: Vector2<f32>,
+) where
+    R: Renderer<'t>,
+{
+    // everything comes out of the one texture
+    draw.texture(atlas.texture());
+
+    for (rect, src) in field_rects(atlas, field, offset) {
+        draw.draw_rect(rect, src);
+    }
+}
+
+/// Gets where every panel in a field with an image goes, and where its image
+/// is in the atlas, followed by an arrow on the edge of a panel for each of
+/// its exits.
+///
+/// The arrows come last so they are drawn over the panels next to them.
+pub fn field_rects<'a, T>(
+    atlas: &'a PanelAtlas<T>,
+    field: &'a Field,
+    offset: Vector2<f32>,
+) -> impl Iterator<Item = (Rect, Rect)> + 'a {
+    let panels = field.iter().filter_map(move |(x, y)| {
+        let pos = Vector2::new(x as f32, y as f32) + offset;
+
+        atlas
+            .region(field.get(x, y).kind)
+            .map(|src| (Rect::UNIT.offset(pos), src))
+    });
+
+    panels.chain(exit_rects(atlas, field, offset))
+}
+
+/// Gets where the arrow for every exit in a field goes, and where it is in
+/// the atlas.
+fn exit_rects<'a, T>(
+    atlas: &'a PanelAtlas<T>,
+    field: &'a Field,
+    offset: Vector2<f32>,
+) -> impl Iterator<Item = (Rect, Rect)> + 'a {
+    /// The size of an arrow, relative to a panel.
+    const SIZE: f32 = 0.3;
+    /// How far an arrow is from the edge of its panel.
+    const MARGIN: f32 = 0.04;
+
+    const MIDDLE: f32 = (1. - SIZE) / 2.;
+    const FAR: f32 = 1. - MARGIN - SIZE;
+
+    field.iter().flat_map(move |(x, y)| {
+        let pos = Vector2::new(x as f32, y as f32) + offset;
+
+        Exits::of(field.get(x, y)).iter().map(move |direction| {
+            let rect = match direction {
+                Direction::Up => Rect::new(MIDDLE, MARGIN, SIZE, SIZE),
+                Direction::Right => Rect::new(FAR, MIDDLE, SIZE, SIZE),
+                Direction::Down => Rect::new(MIDDLE, FAR, SIZE, SIZE),
+                Direction::Left => Rect::new(MARGIN, MIDDLE, SIZE, SIZE),
+            };
+
+            (rect.offset(pos), atlas.arrow(direction))
+        })
+    })
+}
+
+/// Draws a line between every cell on the screen.
+fn draw_grid<'t, R>(
+    draw: &mut R,
+    view: &EditorView,
+    canvas_size: &Vector2<f32>,
+    line_size: Vector2<f32>,
+    color: &Color,
+) where
+    R: Renderer<'t>,
+{
+    // the cells in the corners of the screen
+    let (left, top) = view.pos(&Vector2::zeros());
+    let (right, bottom) = view.pos(canvas_size);
+
+    let width = (right - left + 1) as f32;
+    let height = (bottom - top + 1) as f32;
+
+    // center the lines on the cell borders
+    for x in left..=right {
+        let rect = Rect::new(x as f32 - line_size.x / 2., top as f32, line_size.x, height);
+        draw.fill_rect(rect, color);
+    }
+
+    for y in top..=bottom {
+        let rect = Rect::new(left as f32, y as f32 - line_size.y / 2., width, line_size.y);
+        draw.fill_rect(rect, color);
+    }
+}
@@ -5,6 +5,8 @@
 
 use yew::text_format;
 
+pub use citrus_core::format::*;
+
 /// A wrapper for the RON format.
 pub struct Ron<T>(pub T);
 
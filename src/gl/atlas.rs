@@ -0,0 +1,163 @@
+//! Packing many images into one texture.
+//!
+//! Switching textures between draws is slow, so images that are drawn a lot
+//! together should be drawn out of one big texture instead.
+
+use super::{GLTexture, Rect, GL};
+use web_sys::{HtmlImageElement, WebGlRenderingContext as WebGl};
+
+/// Empty space left around every image in an atlas, in pixels.
+///
+/// Textures are filtered, so without this the edges of images would pick up
+/// the edges of their neighbours.
+pub const PADDING: u32 = 2;
+
+/// Lays out rectangles of the given sizes in rows, so they don't overlap.
+///
+/// Returns the size of the whole layout, and the position of every rectangle
+/// in the same order as `sizes`. Every rectangle has [`PADDING`] around it.
+pub fn pack(sizes: &[(u32, u32)]) -> ((u32, u32), Vec<(u32, u32)>) {
+    let padded = |(width, height): (u32, u32)| (width + PADDING * 2, height + PADDING * 2);
+
+    // aim for a square, but always fit the widest rectangle
+    let area: u32 = sizes
+        .iter()
+        .map(|&size| padded(size))
+        .map(|(width, height)| width * height)
+        .sum();
+    let widest = sizes.iter().map(|&size| padded(size).0).max().unwrap_or(0);
+    let max_width = widest.max((area as f32).sqrt().ceil() as u32);
+
+    // tallest first keeps rows from wasting space
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y) = (0, 0);
+    let mut row_height = 0;
+    let mut width = 0;
+
+    for i in order {
+        let (w, h) = padded(sizes[i]);
+
+        // start a new row
+        if x + w > max_width {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+
+        positions[i] = (x + PADDING, y + PADDING);
+
+        x += w;
+        row_height = row_height.max(h);
+        width = width.max(x);
+    }
+
+    ((width, y + row_height), positions)
+}
+
+/// An image to pack into an atlas.
+pub enum AtlasImage<'a> {
+    /// A loaded image.
+    Element(&'a HtmlImageElement),
+    /// Raw RGBA pixels, row by row.
+    Pixels {
+        width: u32,
+        height: u32,
+        pixels: &'a [u8],
+    },
+}
+
+impl<'a> AtlasImage<'a> {
+    fn size(&self) -> (u32, u32) {
+        match self {
+            AtlasImage::Element(image) => (image.width(), image.height()),
+            AtlasImage::Pixels { width, height, .. } => (*width, *height),
+        }
+    }
+}
+
+impl GL {
+    /// Packs images into a single texture.
+    ///
+    /// Returns the texture, and where every image is in it in pixels, in the
+    /// same order as `images`.
+    ///
+    /// # Panics
+    /// Panics if any image element is not complete, or any pixels are not
+    /// `width * height * 4` bytes long.
+    pub fn create_atlas(&self, images: &[AtlasImage]) -> (GLTexture, Vec<Rect>) {
+        for image in images {
+            match image {
+                AtlasImage::Element(image) => assert!(image.complete(), "image is not complete!"),
+                AtlasImage::Pixels {
+                    width,
+                    height,
+                    pixels,
+                } => assert_eq!(pixels.len(), (width * height * 4) as usize),
+            }
+        }
+
+        let sizes: Vec<_> = images.iter().map(AtlasImage::size).collect();
+        let ((width, height), positions) = pack(&sizes);
+
+        // start out transparent, so the padding stays empty
+        let texture = self.pixel_texture(
+            width.max(1),
+            height.max(1),
+            &vec![0; (width.max(1) * height.max(1) * 4) as usize],
+        );
+
+        self.0.bind_texture(WebGl::TEXTURE_2D, Some(&texture));
+
+        for (image, &(x, y)) in images.iter().zip(positions.iter()) {
+            match image {
+                AtlasImage::Element(image) => self.0.tex_sub_image_2d_with_u32_and_u32_and_image(
+                    WebGl::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    WebGl::RGBA,
+                    WebGl::UNSIGNED_BYTE,
+                    image,
+                ),
+                AtlasImage::Pixels {
+                    width,
+                    height,
+                    pixels,
+                } => self
+                    .0
+                    .tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(
+                        WebGl::TEXTURE_2D,
+                        0,
+                        x as i32,
+                        y as i32,
+                        *width as i32,
+                        *height as i32,
+                        WebGl::RGBA,
+                        WebGl::UNSIGNED_BYTE,
+                        Some(pixels),
+                    ),
+            }
+            .unwrap();
+        }
+
+        // pull every region in by half a pixel, so filtering never samples
+        // the padding around it
+        let regions = sizes
+            .iter()
+            .zip(positions)
+            .map(|(&(w, h), (x, y))| {
+                Rect::new(
+                    x as f32 + 0.5,
+                    y as f32 + 0.5,
+                    (w as f32 - 1.).max(0.),
+                    (h as f32 - 1.).max(0.),
+                )
+            })
+            .collect();
+
+        (texture, regions)
+    }
+}
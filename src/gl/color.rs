@@ -1,12 +1,14 @@
 /// A color with 8 bit components.
 ///
 /// Stored internally as a little endian integer.
+#[derive(Clone, Copy, PartialEq)]
 pub struct Color(u32);
 
 impl Color {
     pub const RED: Color = Color::rgb(u8::MAX, u8::MIN, u8::MIN);
     pub const GREEN: Color = Color::rgb(u8::MIN, u8::MAX, u8::MIN);
     pub const BLUE: Color = Color::rgb(u8::MIN, u8::MIN, u8::MAX);
+    pub const WHITE: Color = Color::rgb(u8::MAX, u8::MAX, u8::MAX);
     pub const ALPHA: Color = Color::rgba(u8::MIN, u8::MIN, u8::MIN, u8::MIN);
 
     pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
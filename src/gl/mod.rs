@@ -9,6 +9,7 @@ pub mod shapes;
 pub mod error;
 pub mod shader;
 pub mod util;
+pub mod atlas;
 
 pub use shapes::*;
 pub use color::*;
@@ -16,8 +17,8 @@ pub use error::*;
 
 use wasm_bindgen::JsCast as _;
 use web_sys::{
-    HtmlCanvasElement, HtmlImageElement, WebGlBuffer, WebGlProgram, WebGlRenderingContext as WebGl,
-    WebGlShader, WebGlTexture, WebGlUniformLocation,
+    HtmlCanvasElement, HtmlImageElement, WebGlBuffer, WebGlFramebuffer, WebGlProgram,
+    WebGlRenderingContext as WebGl, WebGlShader, WebGlTexture, WebGlUniformLocation,
 };
 
 use na::Matrix4;
@@ -100,6 +101,20 @@ impl GL {
     /// Create a texture whose contents are a single pixel defined by a
     /// [`Color`].
     pub fn solid_color_texture(&self, color: Color) -> GLTexture {
+        self.pixel_texture(
+            1,
+            1,
+            &[color.red(), color.green(), color.blue(), color.alpha()],
+        )
+    }
+
+    /// Create a texture from raw RGBA pixels, row by row.
+    ///
+    /// # Panics
+    /// Panics if `pixels` is not `width * height * 4` bytes long.
+    pub fn pixel_texture(&self, width: u32, height: u32, pixels: &[u8]) -> GLTexture {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
         let texture = self.0.create_texture().unwrap();
         let mut texture = GLTexture::new(self.clone_ref(), texture);
         self.0.bind_texture(WebGl::TEXTURE_2D, Some(&texture));
@@ -110,20 +125,106 @@ impl GL {
                 WebGl::TEXTURE_2D,
                 0,
                 WebGl::RGBA as i32,
-                1,
-                1,
+                width as i32,
+                height as i32,
                 0,
                 WebGl::RGBA,
                 WebGl::UNSIGNED_BYTE,
-                Some(&[color.red(), color.green(), color.blue(), color.alpha()]),
+                Some(pixels),
             )
             .unwrap();
 
-        texture.update_size(1., 1.);
+        texture.update_size(width as f32, height as f32);
+
+        // there are no mipmaps, so the default filter would leave the texture
+        // incomplete
+        self.0.tex_parameteri(
+            WebGl::TEXTURE_2D,
+            WebGl::TEXTURE_WRAP_S,
+            WebGl::CLAMP_TO_EDGE as i32,
+        );
+        self.0.tex_parameteri(
+            WebGl::TEXTURE_2D,
+            WebGl::TEXTURE_WRAP_T,
+            WebGl::CLAMP_TO_EDGE as i32,
+        );
+        self.0.tex_parameteri(
+            WebGl::TEXTURE_2D,
+            WebGl::TEXTURE_MIN_FILTER,
+            WebGl::LINEAR as i32,
+        );
 
         texture
     }
 
+    /// Creates a framebuffer to draw to instead of the canvas.
+    ///
+    /// Everything is drawn to the framebuffer until it is dropped. Returns
+    /// `None` if the framebuffer can't be drawn to, usually because it's too
+    /// big.
+    pub fn render_target(&self, width: u32, height: u32) -> Option<GLFramebuffer> {
+        let texture = self.0.create_texture()?;
+        let mut texture = GLTexture::new(self.clone_ref(), texture);
+        self.0.bind_texture(WebGl::TEXTURE_2D, Some(&texture));
+
+        self.0
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                WebGl::TEXTURE_2D,
+                0,
+                WebGl::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                WebGl::RGBA,
+                WebGl::UNSIGNED_BYTE,
+                None,
+            )
+            .ok()?;
+
+        texture.update_size(width as f32, height as f32);
+
+        let framebuffer = self.0.create_framebuffer()?;
+        let framebuffer = GLFramebuffer {
+            gl: self.clone_ref(),
+            framebuffer,
+            texture,
+        };
+
+        self.0
+            .bind_framebuffer(WebGl::FRAMEBUFFER, Some(&framebuffer.framebuffer));
+        self.0.framebuffer_texture_2d(
+            WebGl::FRAMEBUFFER,
+            WebGl::COLOR_ATTACHMENT0,
+            WebGl::TEXTURE_2D,
+            Some(&framebuffer.texture),
+            0,
+        );
+
+        if self.0.check_framebuffer_status(WebGl::FRAMEBUFFER) == WebGl::FRAMEBUFFER_COMPLETE {
+            Some(framebuffer)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the biggest [`render_target`](GL::render_target) that can be
+    /// made on either side, in pixels.
+    ///
+    /// This is the smaller of the biggest texture and the biggest
+    /// renderbuffer.
+    pub fn max_render_target_size(&self) -> u32 {
+        let get = |param: u32| {
+            self.0
+                .get_parameter(param)
+                .ok()
+                .and_then(|value| value.as_f64())
+                .map(|value| value as u32)
+                .unwrap_or(0)
+        };
+
+        get(WebGl::MAX_TEXTURE_SIZE).min(get(WebGl::MAX_RENDERBUFFER_SIZE))
+    }
+
     /// Sets a uniform at the specified location to some [`Matrix3`].
     pub fn uniform_matrix3(&self, uniform: &GLUniformLocation, mat: &na::Matrix3<f32>) {
         self.0
@@ -142,6 +243,24 @@ impl GL {
         buffer
     }
 
+    /// Creates a new buffer that is meant to be rewritten often.
+    ///
+    /// The buffer starts out empty; fill it with
+    /// [`stream_buffer_data`](GL::stream_buffer_data).
+    pub fn create_stream_buffer(&self) -> GLBuffer {
+        GLBuffer(self.clone_ref(), self.0.create_buffer().unwrap())
+    }
+
+    /// Replaces the contents of a buffer made with
+    /// [`create_stream_buffer`](GL::create_stream_buffer).
+    pub fn stream_buffer_data(&self, buf: &GLBuffer, data: &[f32]) {
+        let data = js_sys::Float32Array::from(data);
+
+        self.0.bind_buffer(GL::ARRAY_BUFFER, Some(buf));
+        self.0
+            .buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &data, GL::STREAM_DRAW);
+    }
+
     /// Gets the location of a uniform specified by name.
     pub fn get_uniform_location(
         &self,
@@ -223,6 +342,24 @@ impl GL {
         self.0.enable_vertex_attrib_array(pos);
     }
 
+    /// Binds part of a [`GLBuffer`] of interleaved `vec2`s to an attribute.
+    ///
+    /// `stride` and `offset` are in floats, not bytes.
+    pub fn attribute_buffer_interleaved(&self, buf: &GLBuffer, pos: u32, stride: i32, offset: i32) {
+        const FLOAT_SIZE: i32 = std::mem::size_of::<f32>() as i32;
+
+        self.0.bind_buffer(GL::ARRAY_BUFFER, Some(buf));
+        self.0.vertex_attrib_pointer_with_i32(
+            pos,
+            2,
+            GL::FLOAT,
+            false,
+            stride * FLOAT_SIZE,
+            offset * FLOAT_SIZE,
+        );
+        self.0.enable_vertex_attrib_array(pos);
+    }
+
     /// Binds a [`GLTexture`] to a uniform location.
     pub fn uniform_tex(&self, tex: &GLTexture, pos: &GLUniformLocation, bind: usize) {
         debug_assert!(bind < 32);
@@ -232,6 +369,24 @@ impl GL {
         self.0.uniform1i(Some(pos), bind as i32);
     }
 
+    /// Sets a `bool` uniform location.
+    pub fn uniform_bool(&self, value: bool, pos: &GLUniformLocation) {
+        self.0.uniform1i(Some(pos), value as i32);
+    }
+
+    /// Attributes a [`Color`] to a `vec4` uniform location.
+    pub fn uniform_color(&self, color: &Color, pos: &GLUniformLocation) {
+        let channel = |c: u8| c as f32 / u8::MAX as f32;
+
+        self.0.uniform4f(
+            Some(pos),
+            channel(color.red()),
+            channel(color.green()),
+            channel(color.blue()),
+            channel(color.alpha()),
+        );
+    }
+
     /// Attributes a [`Matrix4`] to a uniform location.
     pub fn uniform_mat4(&self, mat: &Matrix4<f32>, pos: &GLUniformLocation) {
         self.0
@@ -333,6 +488,48 @@ impl Deref for GLProgram {
     }
 }
 
+/// Framebuffer handle, with a texture to draw to.
+///
+/// The framebuffer is bound for as long as this exists.
+pub struct GLFramebuffer {
+    gl: WebGl,
+    framebuffer: WebGlFramebuffer,
+    texture: GLTexture,
+}
+
+impl GLFramebuffer {
+    /// Reads every pixel drawn, as RGBA.
+    ///
+    /// Rows are read from the bottom up, like everything else in GL.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let width = self.texture.width() as i32;
+        let height = self.texture.height() as i32;
+        let mut pixels = vec![0; (width * height * 4) as usize];
+
+        self.gl
+            .read_pixels_with_opt_u8_array(
+                0,
+                0,
+                width,
+                height,
+                WebGl::RGBA,
+                WebGl::UNSIGNED_BYTE,
+                Some(&mut pixels),
+            )
+            .unwrap();
+
+        pixels
+    }
+}
+
+impl Drop for GLFramebuffer {
+    fn drop(&mut self) {
+        // go back to drawing to the canvas
+        self.gl.bind_framebuffer(WebGl::FRAMEBUFFER, None);
+        self.gl.delete_framebuffer(Some(&self.framebuffer));
+    }
+}
+
 /// Buffer handle.
 pub struct GLBuffer(WebGl, WebGlBuffer);
 
@@ -383,6 +580,7 @@ impl GL {
     reexport!(STATIC_DRAW);
     reexport!(STREAM_DRAW);
     reexport!(TRIANGLE_STRIP);
+    reexport!(TRIANGLES);
     reexport!(BLEND);
     reexport!(SRC_ALPHA);
     reexport!(ONE);
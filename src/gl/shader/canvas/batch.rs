@@ -0,0 +1,63 @@
+use crate::gl::*;
+
+/// How many floats make up a vertex: a position, then a texture coordinate.
+pub(super) const VERTEX_SIZE: i32 = 4;
+
+/// Many textured rectangles, drawn all at once with
+/// [`Renderer::draw_batch`](crate::render::Renderer::draw_batch).
+///
+/// Drawing a batch is a single draw call, no matter how many rectangles are
+/// in it, so it should be used for anything drawn a lot of times out of one
+/// texture.
+pub struct SpriteBatch {
+    pub(super) buffer: GLBuffer,
+    vertices: Vec<f32>,
+}
+
+impl SpriteBatch {
+    /// Creates an empty batch.
+    pub fn new(gl: &GL) -> SpriteBatch {
+        SpriteBatch {
+            buffer: gl.create_stream_buffer(),
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Replaces everything in the batch.
+    ///
+    /// Each item is the rectangle to draw to, and the rectangle of the
+    /// texture to draw out of, in pixels like [`DrawCommand::draw_rect`].
+    ///
+    /// [`DrawCommand::draw_rect`]: super::DrawCommand::draw_rect
+    pub fn set(&mut self, gl: &GL, rects: impl IntoIterator<Item = (Rect, Rect)>) {
+        self.vertices.clear();
+
+        for (rect, src) in rects {
+            let corner = |x: f32, y: f32| {
+                [
+                    rect.x + rect.width * x,
+                    rect.y + rect.height * y,
+                    src.x + src.width * x,
+                    src.y + src.height * y,
+                ]
+            };
+
+            // two triangles
+            for &(x, y) in &[(0., 0.), (1., 0.), (0., 1.), (0., 1.), (1., 0.), (1., 1.)] {
+                self.vertices.extend_from_slice(&corner(x, y));
+            }
+        }
+
+        gl.stream_buffer_data(&self.buffer, &self.vertices);
+    }
+
+    /// The amount of vertices in the batch.
+    pub(super) fn vertex_count(&self) -> i32 {
+        self.vertices.len() as i32 / VERTEX_SIZE
+    }
+
+    /// Checks if there is nothing to draw.
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
@@ -1,9 +1,15 @@
 precision mediump float;
 
 uniform sampler2D uTexture;
+uniform vec4 uTint;
+uniform bool uSolid;
 
 varying highp vec2 vTexCoord;
 
 void main() {
-    gl_FragColor = texture2D(uTexture, vTexCoord);
+    if (uSolid) {
+        gl_FragColor = uTint;
+    } else {
+        gl_FragColor = texture2D(uTexture, vTexCoord) * uTint;
+    }
 }
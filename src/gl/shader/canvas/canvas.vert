@@ -1,13 +1,21 @@
 precision mediump float;
 
 attribute vec4 aUnitPos;
+attribute vec2 aTexCoord;
 
 uniform mat4 uWorldMatrix;
 uniform mat4 uTextureMatrix;
+uniform bool uBatched;
 
 varying highp vec2 vTexCoord;
 
 void main() {
     gl_Position = uWorldMatrix * aUnitPos;
-    vTexCoord = (uTextureMatrix * aUnitPos).xy;
+
+    if (uBatched) {
+        // batched vertices bring their own texture coordinates
+        vTexCoord = (uTextureMatrix * vec4(aTexCoord, 0., 1.)).xy;
+    } else {
+        vTexCoord = (uTextureMatrix * aUnitPos).xy;
+    }
 }
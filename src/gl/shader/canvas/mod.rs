@@ -1,5 +1,10 @@
+mod batch;
+
+pub use batch::SpriteBatch;
+
 use crate::gl::shader::Shader;
 use crate::gl::*;
+use crate::render::Renderer;
 
 use na::{Matrix4, Orthographic3, Vector3};
 
@@ -44,10 +49,14 @@ struct CanvasShaderProgram {
     program: GLProgram,
     // uniforms
     texture: GLUniformLocation,
+    tint: GLUniformLocation,
+    solid: GLUniformLocation,
+    batched: GLUniformLocation,
     world_transform: GLUniformLocation,
     tex_transform: GLUniformLocation,
     // attributes
     pos: u32,
+    tex_coord: u32,
     // static data
     unit_square: GLBuffer,
 }
@@ -77,8 +86,12 @@ impl CanvasShaderProgram {
 
         Ok(CanvasShaderProgram {
             pos: gl.get_attrib_location(&program, "aUnitPos") as u32,
+            tex_coord: gl.get_attrib_location(&program, "aTexCoord") as u32,
 
             texture: gl.get_uniform_location(&program, "uTexture")?,
+            tint: gl.get_uniform_location(&program, "uTint")?,
+            solid: gl.get_uniform_location(&program, "uSolid")?,
+            batched: gl.get_uniform_location(&program, "uBatched")?,
             world_transform: gl.get_uniform_location(&program, "uWorldMatrix")?,
             tex_transform: gl.get_uniform_location(&program, "uTextureMatrix")?,
 
@@ -104,6 +117,7 @@ pub struct DrawCommand<'a, 'b> {
     transform: Matrix4<f32>,
     program: &'a mut CanvasShaderProgram,
     current_tex: Option<&'b GLTexture>,
+    tint: Color,
 }
 
 impl<'a, 'b> DrawCommand<'a, 'b> {
@@ -119,35 +133,73 @@ impl<'a, 'b> DrawCommand<'a, 'b> {
             .gl
             .attribute_buffer(&program.unit_square, program.pos);
 
+        // draw textures as they are
+        program.gl.uniform_color(&Color::WHITE, &program.tint);
+        program.gl.uniform_bool(false, &program.solid);
+        program.gl.uniform_bool(false, &program.batched);
+
         DrawCommand {
             projection: *projection,
             transform: Matrix4::identity(),
             program,
             current_tex: None,
+            tint: Color::WHITE,
         }
     }
 
-    pub fn set_transform(&mut self, transform: &Matrix4<f32>) {
+    fn bind_world_transform(&self, rect: Rect) {
+        self.program.gl.uniform_mat4(
+            &(self.projection.into_inner()
+                * self.transform
+                * Matrix4::new_translation(&Vector3::new(rect.x, rect.y, 0.))
+                    .prepend_nonuniform_scaling(&Vector3::new(rect.width, rect.height, 1.))),
+            &self.program.world_transform,
+        );
+    }
+}
+
+impl<'a, 'b> Renderer<'b> for DrawCommand<'a, 'b> {
+    type Texture = GLTexture;
+    type Batch = SpriteBatch;
+
+    fn clear(&mut self) {
+        self.program.clear();
+    }
+
+    fn set_transform(&mut self, transform: &Matrix4<f32>) {
         self.transform = *transform;
     }
 
-    pub fn texture(&mut self, tex: &'b GLTexture) {
+    fn set_tint(&mut self, tint: &Color) {
+        self.program.gl.uniform_color(tint, &self.program.tint);
+        self.tint = *tint;
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: &Color) {
+        self.program.gl.uniform_bool(true, &self.program.solid);
+        self.program.gl.uniform_color(color, &self.program.tint);
+
+        self.bind_world_transform(rect);
+        self.program.gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+
+        // go back to drawing textures
+        self.program.gl.uniform_bool(false, &self.program.solid);
+        self.program
+            .gl
+            .uniform_color(&self.tint, &self.program.tint);
+    }
+
+    fn texture(&mut self, tex: &'b GLTexture) {
         self.program.gl.uniform_tex(tex, &self.program.texture, 0);
         self.current_tex = Some(tex);
     }
 
-    pub fn draw_full_rect(&mut self, rect: Rect) {
+    fn draw_full_rect(&mut self, rect: Rect) {
         self.current_tex.as_ref()
             .expect("Failed to draw with no active texture!");
 
         // bind the world matrix
-        self.program.gl.uniform_mat4(
-            &(self.projection.into_inner()
-                * self.transform
-                * Matrix4::new_translation(&Vector3::new(rect.x, rect.y, 0.))
-                    .prepend_nonuniform_scaling(&Vector3::new(rect.width, rect.height, 1.))),
-            &self.program.world_transform,
-        );
+        self.bind_world_transform(rect);
         // bind the texture transform matrix
         self.program.gl.uniform_mat4(
             &Matrix4::identity(),
@@ -158,18 +210,12 @@ impl<'a, 'b> DrawCommand<'a, 'b> {
         self.program.gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
     }
 
-    pub fn draw_rect(&mut self, rect: Rect, src: Rect) {
+    fn draw_rect(&mut self, rect: Rect, src: Rect) {
         let tex = self.current_tex.as_ref()
             .expect("Failed to draw with no active texture!");
 
         // bind the world matrix
-        self.program.gl.uniform_mat4(
-            &(self.projection.into_inner()
-                * self.transform
-                * Matrix4::new_translation(&Vector3::new(rect.x, rect.y, 0.))
-                    .prepend_nonuniform_scaling(&Vector3::new(rect.width, rect.height, 1.))),
-            &self.program.world_transform,
-        );
+        self.bind_world_transform(rect);
         // bind the texture transform matrix
         self.program.gl.uniform_mat4(
             &(Matrix4::new_translation(&Vector3::new(src.x / tex.width(), src.y / tex.height(), 0.))
@@ -180,4 +226,45 @@ impl<'a, 'b> DrawCommand<'a, 'b> {
         // draw
         self.program.gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
     }
+
+    fn draw_batch(&mut self, batch: &SpriteBatch) {
+        let tex = self
+            .current_tex
+            .as_ref()
+            .expect("Failed to draw with no active texture!");
+
+        if batch.is_empty() {
+            return;
+        }
+
+        let gl = &self.program.gl;
+
+        // batches are already positioned, so only the view is needed
+        self.bind_world_transform(Rect::UNIT);
+        // texture coordinates are in pixels
+        gl.uniform_mat4(
+            &Matrix4::new_nonuniform_scaling(&Vector3::new(
+                1. / tex.width(),
+                1. / tex.height(),
+                1.,
+            )),
+            &self.program.tex_transform,
+        );
+        gl.uniform_bool(true, &self.program.batched);
+
+        gl.attribute_buffer_interleaved(&batch.buffer, self.program.pos, batch::VERTEX_SIZE, 0);
+        gl.attribute_buffer_interleaved(
+            &batch.buffer,
+            self.program.tex_coord,
+            batch::VERTEX_SIZE,
+            2,
+        );
+
+        gl.draw_arrays(GL::TRIANGLES, 0, batch.vertex_count());
+
+        // go back to drawing unit squares
+        gl.disable_vertex_attrib_array(self.program.tex_coord);
+        gl.attribute_buffer(&self.program.unit_square, self.program.pos);
+        gl.uniform_bool(false, &self.program.batched);
+    }
 }
@@ -26,6 +26,19 @@ impl Rect {
         self.y + self.height
     }
 
+    /// Gets the four edges of this rectangle as thin rectangles inside of
+    /// it, with `size` as the thickness of the edges.
+    pub fn outline(&self, size: na::Vector2<f32>) -> [Rect; 4] {
+        [
+            // top, bottom
+            Rect::new(self.x, self.y, self.width, size.y),
+            Rect::new(self.x, self.far_y() - size.y, self.width, size.y),
+            // left, right
+            Rect::new(self.x, self.y, size.x, self.height),
+            Rect::new(self.far_x() - size.x, self.y, size.x, self.height),
+        ]
+    }
+
     /// Offsets this rectangle.
     pub fn offset(self, offset: na::Vector2<f32>) -> Rect {
         Rect {
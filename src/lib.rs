@@ -1,25 +1,32 @@
-#![recursion_limit = "256"]
 // NOTE: if elses are sometimes verbose to better communicate code intent.
 #![allow(clippy::collapsible_else_if)]
 
 extern crate nalgebra as na;
 
 pub mod editor;
-pub mod enum_map;
 pub mod format;
 pub mod gl;
+pub mod library;
+pub mod render;
+pub mod stats;
 pub mod util;
+pub mod validate;
 
 #[cfg(test)]
 mod tests;
 
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
 
 use yew::prelude::*;
-use yew::services::storage::{Area, StorageService};
+use yew::services::reader::{File, FileData, ReaderService, ReaderTask};
+use yew::services::DialogService;
 
-use editor::{EditorView, FieldEditor};
-use format::Ron;
+use citrus_core::EditorView;
+use editor::FieldEditor;
+use library::{list::Action, FieldList, Library};
+use stats::{Statistics, StatisticsPanel};
+use validate::{Rules, Validation, ValidationReport};
 
 // When the `wee_alloc` feature is enabled, this uses `wee_alloc` as the global
 // allocator.
@@ -33,11 +40,23 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 pub struct Runtime {
     link: ComponentLink<Self>,
 
+    library: Library,
+    current: u32,
     view: EditorView,
+    validation: Validation,
+
+    reader: ReaderService,
+    _reader_task: Option<ReaderTask>,
 }
 
 pub enum Msg {
-    Update(EditorView),
+    Update(Box<EditorView>),
+    Library(Action),
+    Import(File),
+    Imported(FileData),
+    PasteShareCode(String),
+    Focus((usize, usize)),
+    SetPlayers(usize),
 }
 
 impl Component for Runtime {
@@ -45,21 +64,35 @@ impl Component for Runtime {
     type Properties = ();
 
     fn create(_props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut library = Library::open();
+        // a linked field takes priority, otherwise load views from storage
+        let (current, view) = Runtime::open_location(&mut library)
+            .unwrap_or_else(|| Runtime::open_field(&mut library));
+
         Runtime {
             link,
-            // load views from storage
-            view: Runtime::load_storage().unwrap_or_else(EditorView::new_example),
+            library,
+            current,
+            validation: Validation::new(&view.field, Rules::default()),
+            view,
+            reader: ReaderService::new(),
+            _reader_task: None,
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
-        match msg {
-            Msg::Update(view) => {
-                Runtime::save_storage(&view);
-            }
-        }
+        let render = self.handle(msg);
 
-        false
+        // only check the field again once it has changed
+        self.validation.update(&self.view.field);
+
+        render
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        // the editor has its own copy of the view now, and it shouldn't
+        // focus again every time something else changes
+        self.view.needs_focus = None;
     }
 
     fn change(&mut self, _props: Self::Properties) -> ShouldRender {
@@ -68,29 +101,286 @@ impl Component for Runtime {
 
     fn view(&self) -> Html {
         html! {
-            <FieldEditor view=self.view.clone()
-                         onupdate=self.link.callback(Msg::Update) />
+            <>
+                <FieldEditor view=self.view.clone()
+                             onupdate=self.link.callback(|view| Msg::Update(Box::new(view)))
+                             onimport=self.link.callback(Msg::Import)
+                             onexport=self.link.callback(|_| Msg::Library(Action::Export)) />
+                { self.view_side_bar() }
+            </>
         }
     }
 }
 
 impl Runtime {
-    fn load_storage() -> Option<EditorView> {
-        // TODO: add more storage entries
-        // for now, one or zero will do.
-        let storage = StorageService::new(Area::Local).unwrap();
-
-        // restore our value
-        storage
-            .restore::<Ron<Result<EditorView, anyhow::Error>>>("cached_field")
-            .0
-            .ok()
+    fn handle(&mut self, msg: Msg) -> ShouldRender {
+        match msg {
+            Msg::Update(view) => {
+                self.library.save(self.current, &view);
+                self.view = *view;
+
+                // update the side bar
+                true
+            }
+            Msg::Focus(cell) => {
+                self.view.needs_focus = Some(cell);
+
+                true
+            }
+            Msg::SetPlayers(players) => {
+                self.validation.set_rules(Rules { players });
+
+                true
+            }
+            Msg::Library(action) => {
+                self.update_library(action);
+
+                true
+            }
+            Msg::Import(file) => {
+                let callback = self.link.callback(Msg::Imported);
+
+                match self.reader.read_file(file, callback) {
+                    Ok(task) => self._reader_task = Some(task),
+                    Err(err) => DialogService::alert(&format!("Could not read file: {}", err)),
+                }
+
+                false
+            }
+            Msg::Imported(data) => {
+                self._reader_task = None;
+
+                match EditorView::from_fldx(&data.content) {
+                    Ok(view) => {
+                        self.open_view(data.name.trim_end_matches(".fldx"), view);
+
+                        true
+                    }
+                    Err(err) => {
+                        DialogService::alert(&format!("Could not open {}: {}", data.name, err));
+
+                        false
+                    }
+                }
+            }
+            Msg::PasteShareCode(code) => match EditorView::from_base64(&code) {
+                Ok(view) => {
+                    self.open_view(Self::SHARED_NAME, view);
+
+                    true
+                }
+                Err(err) => {
+                    DialogService::alert(&format!("Invalid share code: {}", err));
+
+                    false
+                }
+            },
+        }
+    }
+
+    fn view_side_bar(&self) -> Html {
+        html! {
+            <div class="side-bar">
+                <FieldList entries=self.library.entries().to_vec()
+                           current=Some(self.current)
+                           onaction=self.link.callback(Msg::Library) />
+                <ValidationReport issues=self.validation.issues().to_vec()
+                                  players=self.validation.rules().players
+                                  onselect=self.link.callback(Msg::Focus)
+                                  onplayers=self.link.callback(Msg::SetPlayers) />
+                <StatisticsPanel stats=Statistics::new(&self.view.field) />
+            </div>
+        }
+    }
+
+    const SHARED_NAME: &'static str = "Shared field";
+    const HASH_PREFIX: &'static str = "#field=";
+
+    fn update_library(&mut self, action: Action) {
+        match action {
+            Action::Select(id) => self.switch(id),
+            Action::Import(file) => self.link.send_message(Msg::Import(file)),
+            Action::Export => self.export(),
+            Action::CopyShareCode => {
+                Runtime::copy_text("Copy this share code:", self.view.to_base64());
+            }
+            Action::CopyLink => {
+                let code = self.view.to_base64_config(base64::URL_SAFE_NO_PAD);
+
+                // build the link without touching this page's location, so
+                // reloading doesn't open the field again
+                let location = web_sys::window().unwrap().location();
+                let link = format!(
+                    "{}{}{}{}{}",
+                    location.origin().unwrap_or_default(),
+                    location.pathname().unwrap_or_default(),
+                    location.search().unwrap_or_default(),
+                    Self::HASH_PREFIX,
+                    code
+                );
+
+                Runtime::copy_text("Copy this link:", link);
+            }
+            Action::PasteShareCode => {
+                let link = self.link.clone();
+
+                spawn_local(async move {
+                    let code = match util::read_clipboard().await {
+                        Ok(code) => Some(code),
+                        Err(_) => DialogService::prompt("Paste a share code:", None),
+                    };
+
+                    if let Some(code) = code {
+                        link.send_message(Msg::PasteShareCode(code));
+                    }
+                });
+            }
+            Action::Create => {
+                let id = self
+                    .library
+                    .create(Library::DEFAULT_NAME, &EditorView::new_empty());
+
+                self.switch(id);
+            }
+            Action::Rename(id) => {
+                let name = match self.library.entry(id) {
+                    Some(entry) => &entry.name,
+                    None => return,
+                };
+
+                match DialogService::prompt("Rename field:", Some(name)) {
+                    Some(name) if !name.trim().is_empty() => {
+                        self.library.rename(id, name.trim());
+                    }
+                    _ => (),
+                }
+            }
+            Action::Duplicate(id) => {
+                if let Some(id) = self.library.duplicate(id) {
+                    self.switch(id);
+                }
+            }
+            Action::Delete(id) => {
+                let name = match self.library.entry(id) {
+                    Some(entry) => &entry.name,
+                    None => return,
+                };
+
+                if DialogService::confirm(&format!("Delete \"{}\"?", name)) {
+                    self.library.delete(id);
+
+                    if id == self.current {
+                        let (current, view) = Runtime::open_field(&mut self.library);
+
+                        self.current = current;
+                        self.view = view;
+                    }
+                }
+            }
+        }
+    }
+
+    fn export(&self) {
+        let name = self
+            .library
+            .entry(self.current)
+            .map(|entry| entry.name.as_str())
+            .unwrap_or(Library::DEFAULT_NAME);
+
+        let filename = match DialogService::prompt("Save as:", Some(&format!("{}.fldx", name))) {
+            Some(filename) if !filename.is_empty() => filename,
+            _ => return,
+        };
+
+        if util::download(&filename, &self.view.to_fldx()).is_err() {
+            DialogService::alert("Could not save file.");
+        }
+    }
+
+    /// Copies text to the clipboard, asking the user to copy it themselves if
+    /// that fails.
+    fn copy_text(message: &'static str, text: String) {
+        spawn_local(async move {
+            if util::write_clipboard(&text).await.is_err() {
+                DialogService::prompt(message, Some(&text));
+            }
+        });
     }
 
-    fn save_storage(save: &EditorView) {
-        let mut storage = StorageService::new(Area::Local).unwrap();
+    /// Opens the field linked in the location hash, if there is one.
+    ///
+    /// The field is added to the library, and the hash is cleared so that
+    /// reloading doesn't add it again.
+    fn open_location(library: &mut Library) -> Option<(u32, EditorView)> {
+        let window = web_sys::window().unwrap();
+        let hash = window.location().hash().ok()?;
+        let code = hash.strip_prefix(Self::HASH_PREFIX)?;
+
+        let view = EditorView::from_base64_config(code, base64::URL_SAFE_NO_PAD);
+
+        // clear the hash, leaving the rest of the url alone
+        let location = window.location();
+        let url = format!(
+            "{}{}",
+            location.pathname().unwrap_or_default(),
+            location.search().unwrap_or_default()
+        );
+        let _ = window
+            .history()
+            .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)));
+
+        match view {
+            Ok(view) => {
+                let id = library.create(Self::SHARED_NAME, &view);
+                library.switch(id);
+
+                // keep the original view; it still needs to be centered
+                Some((id, view))
+            }
+            Err(err) => {
+                DialogService::alert(&format!("Invalid field link: {}", err));
+
+                None
+            }
+        }
+    }
+
+    /// Adds a new view to the library and opens it.
+    fn open_view(&mut self, name: &str, view: EditorView) {
+        let id = self.library.create(name, &view);
+
+        self.switch(id);
+        self.view.needs_center = true;
+    }
+
+    fn switch(&mut self, id: u32) {
+        if let Some(view) = self.library.switch(id) {
+            self.current = id;
+            self.view = view;
+        }
+    }
+
+    /// Opens the last edited field, or any field at all if that fails.
+    ///
+    /// If the library is empty, the example field is added to it.
+    fn open_field(library: &mut Library) -> (u32, EditorView) {
+        let ids = library
+            .current()
+            .into_iter()
+            .chain(library.entries().iter().map(|entry| entry.id))
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            if let Some(view) = library.switch(id) {
+                return (id, view);
+            }
+        }
+
+        let view = EditorView::new_example();
+        let id = library.create(Library::DEFAULT_NAME, &view);
+        library.switch(id);
 
-        storage.store::<Ron<&EditorView>>("cached_field", Ron(save));
+        (id, view)
     }
 }
 
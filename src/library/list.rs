@@ -0,0 +1,154 @@
+use web_sys::{File, HtmlInputElement};
+use yew::callback::Callback;
+use yew::prelude::*;
+
+use super::Entry;
+use crate::util;
+
+/// Saved field list component.
+pub struct FieldList {
+    link: ComponentLink<Self>,
+    props: Props,
+    file_input: NodeRef,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub entries: Vec<Entry>,
+    pub current: Option<u32>,
+    pub onaction: Callback<Action>,
+}
+
+/// An operation requested on the library.
+#[derive(Clone)]
+pub enum Action {
+    Select(u32),
+    Create,
+    Import(File),
+    Export,
+    CopyShareCode,
+    PasteShareCode,
+    CopyLink,
+    Rename(u32),
+    Duplicate(u32),
+    Delete(u32),
+}
+
+pub enum Msg {
+    Action(Action),
+    ImportChange(ChangeData),
+}
+
+impl Component for FieldList {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        FieldList {
+            link,
+            props,
+            file_input: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Action(action) => {
+                // bubble
+                self.props.onaction.emit(action);
+            }
+            Msg::ImportChange(ChangeData::Files(files)) => {
+                if let Some(file) = files.get(0) {
+                    self.props.onaction.emit(Action::Import(file));
+                }
+
+                // otherwise, picking the same file again wouldn't be a change
+                if let Some(input) = self.file_input.cast::<HtmlInputElement>() {
+                    input.set_value("");
+                }
+            }
+            Msg::ImportChange(_) => (),
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let button = |action: fn() -> Action, name: &str| {
+            html! {
+                <a class="field-list-button"
+                   href="#"
+                   onclick=util::link_callback(&self.link, move |_| Msg::Action(action()))>
+                    { name }
+                </a>
+            }
+        };
+
+        let import = html! {
+            <label class="field-list-button">
+                { "Open file..." }
+                <input type="file"
+                       accept=".fldx"
+                       onchange=self.link.callback(Msg::ImportChange)
+                       ref=self.file_input.clone() />
+            </label>
+        };
+
+        html! {
+            <div class="field-list">
+                <ul>
+                    { for self.props.entries.iter().map(|entry| self.view_entry(entry)) }
+                </ul>
+                { button(|| Action::Create, "New field") }
+                { import }
+                { button(|| Action::Export, "Save file...") }
+                { button(|| Action::CopyShareCode, "Copy share code") }
+                { button(|| Action::PasteShareCode, "Paste share code") }
+                { button(|| Action::CopyLink, "Copy link") }
+            </div>
+        }
+    }
+}
+
+impl FieldList {
+    fn view_entry(&self, entry: &Entry) -> Html {
+        let id = entry.id;
+
+        let class = if Some(id) == self.props.current {
+            "field-entry selected"
+        } else {
+            "field-entry"
+        };
+
+        let button = |action: fn(u32) -> Action, title: &str, icon: &str| {
+            html! {
+                <a href="#" title=title
+                   onclick=util::link_callback(&self.link, move |_| Msg::Action(action(id)))>
+                    { icon }
+                </a>
+            }
+        };
+
+        html! {
+            <li class=class>
+                <a class="field-name"
+                   href="#"
+                   onclick=util::link_callback(&self.link, move |_| Msg::Action(Action::Select(id)))>
+                    { &entry.name }
+                </a>
+                { button(Action::Rename, "Rename", "✎") }
+                { button(Action::Duplicate, "Duplicate", "⧉") }
+                { button(Action::Delete, "Delete", "✕") }
+            </li>
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! A library of fields saved to local storage.
+
+pub mod list;
+
+pub use list::FieldList;
+
+use serde::{Deserialize, Serialize};
+use yew::services::storage::{Area, StorageService};
+
+use crate::format::Ron;
+use citrus_core::EditorView;
+
+/// An entry in the [`Library`] index.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u32,
+    pub name: String,
+}
+
+/// The index of all saved fields.
+///
+/// Fields themselves are stored under their own keys, so they don't all need
+/// to be deserialized just to list them.
+#[derive(Default, Serialize, Deserialize)]
+struct Index {
+    next_id: u32,
+    current: Option<u32>,
+    entries: Vec<Entry>,
+}
+
+/// A collection of named fields, saved to local storage.
+pub struct Library {
+    storage: StorageService,
+    index: Index,
+}
+
+impl Library {
+    const INDEX_KEY: &'static str = "field_index";
+    /// Where fields used to be stored, before there was more than one.
+    const LEGACY_KEY: &'static str = "cached_field";
+
+    pub const DEFAULT_NAME: &'static str = "Untitled";
+
+    /// Opens the library from local storage.
+    pub fn open() -> Library {
+        let storage = StorageService::new(Area::Local).unwrap();
+
+        let index = storage
+            .restore::<Ron<Result<Index, anyhow::Error>>>(Self::INDEX_KEY)
+            .0
+            .ok();
+
+        let mut library = Library {
+            storage,
+            index: index.unwrap_or_default(),
+        };
+
+        if library.index.entries.is_empty() {
+            library.migrate_legacy();
+        }
+
+        library
+    }
+
+    /// All of the fields in the library, in order of creation.
+    pub fn entries(&self) -> &[Entry] {
+        &self.index.entries
+    }
+
+    /// Gets the id of the field currently being edited.
+    pub fn current(&self) -> Option<u32> {
+        self.index.current
+    }
+
+    /// Gets an entry by id.
+    pub fn entry(&self, id: u32) -> Option<&Entry> {
+        self.index.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Loads a field from the library.
+    pub fn load(&self, id: u32) -> Option<EditorView> {
+        self.storage
+            .restore::<Ron<Result<EditorView, anyhow::Error>>>(&field_key(id))
+            .0
+            .ok()
+    }
+
+    /// Saves a field to the library.
+    pub fn save(&mut self, id: u32, view: &EditorView) {
+        self.storage
+            .store::<Ron<&EditorView>>(&field_key(id), Ron(view));
+    }
+
+    /// Adds a new field to the library, returning its id.
+    pub fn create(&mut self, name: impl Into<String>, view: &EditorView) -> u32 {
+        let id = self.index.next_id;
+        self.index.next_id += 1;
+
+        self.index.entries.push(Entry {
+            id,
+            name: name.into(),
+        });
+
+        self.save(id, view);
+        self.save_index();
+
+        id
+    }
+
+    /// Renames a field.
+    pub fn rename(&mut self, id: u32, name: impl Into<String>) {
+        if let Some(entry) = self.index.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.name = name.into();
+            self.save_index();
+        }
+    }
+
+    /// Copies a field into a new entry, returning the new entry's id.
+    pub fn duplicate(&mut self, id: u32) -> Option<u32> {
+        let name = format!("{} (copy)", self.entry(id)?.name);
+        let view = self.load(id)?;
+
+        Some(self.create(name, &view))
+    }
+
+    /// Removes a field from the library.
+    pub fn delete(&mut self, id: u32) {
+        self.index.entries.retain(|entry| entry.id != id);
+        self.storage.remove(&field_key(id));
+
+        if self.index.current == Some(id) {
+            self.index.current = None;
+        }
+
+        self.save_index();
+    }
+
+    /// Switches the current field, returning it.
+    pub fn switch(&mut self, id: u32) -> Option<EditorView> {
+        let view = self.load(id)?;
+
+        self.index.current = Some(id);
+        self.save_index();
+
+        Some(view)
+    }
+
+    fn save_index(&mut self) {
+        self.storage
+            .store::<Ron<&Index>>(Self::INDEX_KEY, Ron(&self.index));
+    }
+
+    fn migrate_legacy(&mut self) {
+        let legacy = self
+            .storage
+            .restore::<Ron<Result<EditorView, anyhow::Error>>>(Self::LEGACY_KEY)
+            .0;
+
+        if let Ok(view) = legacy {
+            let id = self.create(Self::DEFAULT_NAME, &view);
+            self.index.current = Some(id);
+            self.save_index();
+
+            self.storage.remove(Self::LEGACY_KEY);
+        }
+    }
+}
+
+fn field_key(id: u32) -> String {
+    format!("field/{}", id)
+}
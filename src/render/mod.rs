@@ -0,0 +1,64 @@
+//! Drawing, without caring about what is being drawn to.
+//!
+//! The editor draws through [`Renderer`], so the same drawing code can go to
+//! WebGL in the browser, or to a [`SoftwareRenderer`] in tests.
+
+pub mod software;
+
+pub use software::{SoftwareBatch, SoftwareRenderer, SoftwareTexture};
+
+use crate::gl::{Color, Rect};
+use na::Matrix4;
+
+/// Something that can draw textured and colored rectangles.
+///
+/// Textures are borrowed for `'t`, so a renderer can hold on to the active
+/// texture between draws.
+pub trait Renderer<'t> {
+    /// The kind of texture this draws with.
+    type Texture: 't;
+    /// Many rectangles out of one texture, ready to be drawn all at once.
+    type Batch;
+
+    /// Clears everything drawn so far.
+    fn clear(&mut self);
+
+    /// Sets the transform applied to every rectangle drawn after.
+    fn set_transform(&mut self, transform: &Matrix4<f32>);
+
+    /// Sets a color to multiply textures by.
+    ///
+    /// This stays set for every draw after, until it is set again.
+    fn set_tint(&mut self, tint: &Color);
+
+    /// Sets the texture to draw with.
+    fn texture(&mut self, tex: &'t Self::Texture);
+
+    /// Draws the whole active texture to a rectangle.
+    ///
+    /// # Panics
+    /// Panics if there is no active texture.
+    fn draw_full_rect(&mut self, rect: Rect);
+
+    /// Draws part of the active texture to a rectangle.
+    ///
+    /// `src` is in pixels of the texture.
+    ///
+    /// # Panics
+    /// Panics if there is no active texture.
+    fn draw_rect(&mut self, rect: Rect, src: Rect);
+
+    /// Draws everything in a batch out of the active texture.
+    ///
+    /// This looks the same as drawing each rectangle in the batch with
+    /// [`draw_rect`](Renderer::draw_rect).
+    ///
+    /// # Panics
+    /// Panics if there is no active texture.
+    fn draw_batch(&mut self, batch: &Self::Batch);
+
+    /// Fills a rectangle with a solid color.
+    ///
+    /// This doesn't need an active texture.
+    fn fill_rect(&mut self, rect: Rect, color: &Color);
+}
@@ -0,0 +1,247 @@
+//! A renderer that draws into memory, without a GPU.
+
+use super::Renderer;
+use crate::gl::{Color, Rect};
+use na::{Matrix4, Point3};
+
+use std::ops::Range;
+
+/// An RGBA image in memory, row by row from the top.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SoftwareTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl SoftwareTexture {
+    /// Creates a texture from RGBA pixels.
+    ///
+    /// # Panics
+    /// Panics if `pixels` is not `width * height * 4` bytes long.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> SoftwareTexture {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+        SoftwareTexture {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Gets the pixel at a position.
+    ///
+    /// # Panics
+    /// Panics if the position is outside of the texture.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Gets the nearest pixel to a point in `src`, where the point goes from
+    /// `0` to `1` across it.
+    fn sample(&self, src: Rect, u: f32, v: f32) -> [f32; 4] {
+        let clamp = |x: f32, max: u32| x.floor().max(0.).min(max.saturating_sub(1) as f32) as u32;
+
+        let x = clamp(src.x + src.width * u, self.width);
+        let y = clamp(src.y + src.height * v, self.height);
+
+        let [r, g, b, a] = self.pixel(x, y);
+        [channel(r), channel(g), channel(b), channel(a)]
+    }
+}
+
+/// A batch for a [`SoftwareRenderer`]: where to draw each rectangle, and the
+/// rectangle of the texture to draw out of.
+pub type SoftwareBatch = Vec<(Rect, Rect)>;
+
+/// A [`Renderer`] that draws into a [`SoftwareTexture`].
+///
+/// This blends the same way the WebGL renderer does, but samples textures
+/// with the nearest pixel instead of filtering them, so nothing it draws
+/// depends on a GPU.
+pub struct SoftwareRenderer<'t> {
+    target: SoftwareTexture,
+    transform: Matrix4<f32>,
+    tint: Color,
+    texture: Option<&'t SoftwareTexture>,
+}
+
+impl<'t> SoftwareRenderer<'t> {
+    /// The color the target is cleared to; the same as the WebGL canvas.
+    const CLEAR: Color = Color::rgba(u8::MAX, u8::MAX, u8::MAX, u8::MIN);
+
+    /// Creates a renderer with a cleared target of a size, in pixels.
+    pub fn new(width: u32, height: u32) -> SoftwareRenderer<'t> {
+        let mut renderer = SoftwareRenderer {
+            target: SoftwareTexture::new(width, height, vec![0; (width * height * 4) as usize]),
+            transform: Matrix4::identity(),
+            tint: Color::WHITE,
+            texture: None,
+        };
+
+        renderer.clear();
+        renderer
+    }
+
+    /// Gets everything drawn so far.
+    pub fn target(&self) -> &SoftwareTexture {
+        &self.target
+    }
+
+    /// Takes everything drawn so far.
+    pub fn into_target(self) -> SoftwareTexture {
+        self.target
+    }
+
+    /// Blends in a color for every pixel a rectangle covers.
+    ///
+    /// `shade` gets where the pixel is in the rectangle, from `0` to `1`
+    /// across it.
+    fn draw(&mut self, rect: Rect, shade: impl Fn(f32, f32) -> [f32; 4]) {
+        let start = self
+            .transform
+            .transform_point(&Point3::new(rect.x, rect.y, 0.));
+        let end = self
+            .transform
+            .transform_point(&Point3::new(rect.far_x(), rect.far_y(), 0.));
+
+        for y in covered(start.y, end.y, self.target.height) {
+            for x in covered(start.x, end.x, self.target.width) {
+                let u = (x as f32 + 0.5 - start.x) / (end.x - start.x);
+                let v = (y as f32 + 0.5 - start.y) / (end.y - start.y);
+
+                self.blend(x, y, shade(u, v));
+            }
+        }
+    }
+
+    /// Blends a color over a pixel, like `SRC_ALPHA, ONE_MINUS_SRC_ALPHA`.
+    fn blend(&mut self, x: u32, y: u32, [r, g, b, a]: [f32; 4]) {
+        let i = ((y * self.target.width + x) * 4) as usize;
+        let dst = &mut self.target.pixels[i..i + 4];
+
+        let mix = |src: f32, dst: u8| byte(src * a + channel(dst) * (1. - a));
+
+        dst[0] = mix(r, dst[0]);
+        dst[1] = mix(g, dst[1]);
+        dst[2] = mix(b, dst[2]);
+        // alpha is added, like `ONE, ONE_MINUS_SRC_ALPHA`
+        dst[3] = byte(a + channel(dst[3]) * (1. - a));
+    }
+}
+
+impl<'t> Renderer<'t> for SoftwareRenderer<'t> {
+    type Texture = SoftwareTexture;
+    type Batch = SoftwareBatch;
+
+    fn clear(&mut self) {
+        let clear = [
+            Self::CLEAR.red(),
+            Self::CLEAR.green(),
+            Self::CLEAR.blue(),
+            Self::CLEAR.alpha(),
+        ];
+
+        for pixel in self.target.pixels.chunks_mut(4) {
+            pixel.copy_from_slice(&clear);
+        }
+    }
+
+    fn set_transform(&mut self, transform: &Matrix4<f32>) {
+        self.transform = *transform;
+    }
+
+    fn set_tint(&mut self, tint: &Color) {
+        self.tint = *tint;
+    }
+
+    fn texture(&mut self, tex: &'t SoftwareTexture) {
+        self.texture = Some(tex);
+    }
+
+    fn draw_full_rect(&mut self, rect: Rect) {
+        let tex = self
+            .texture
+            .expect("Failed to draw with no active texture!");
+
+        self.draw_rect(
+            rect,
+            Rect::new(0., 0., tex.width() as f32, tex.height() as f32),
+        );
+    }
+
+    fn draw_rect(&mut self, rect: Rect, src: Rect) {
+        let tex = self
+            .texture
+            .expect("Failed to draw with no active texture!");
+        let tint = channels(&self.tint);
+
+        self.draw(rect, |u, v| {
+            let color = tex.sample(src, u, v);
+            [
+                color[0] * tint[0],
+                color[1] * tint[1],
+                color[2] * tint[2],
+                color[3] * tint[3],
+            ]
+        });
+    }
+
+    fn draw_batch(&mut self, batch: &SoftwareBatch) {
+        for &(rect, src) in batch {
+            self.draw_rect(rect, src);
+        }
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: &Color) {
+        let color = channels(color);
+
+        self.draw(rect, |_, _| color);
+    }
+}
+
+/// Gets the pixels between two edges, out of `0..max`.
+///
+/// A pixel is covered if its middle is.
+fn covered(a: f32, b: f32, max: u32) -> Range<u32> {
+    let edge = |x: f32| ((x - 0.5).ceil().max(0.) as u32).min(max);
+
+    edge(a.min(b))..edge(a.max(b))
+}
+
+fn channels(color: &Color) -> [f32; 4] {
+    [
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue()),
+        channel(color.alpha()),
+    ]
+}
+
+fn channel(c: u8) -> f32 {
+    c as f32 / u8::MAX as f32
+}
+
+fn byte(c: f32) -> u8 {
+    (c * u8::MAX as f32).round() as u8
+}
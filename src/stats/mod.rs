@@ -0,0 +1,47 @@
+//! Panel statistics, for balancing fields.
+
+pub mod panel;
+
+pub use panel::StatisticsPanel;
+
+use citrus_common::field::Field;
+use citrus_common::PanelKind;
+
+use crate::editor::assets::PanelMap;
+
+/// A tally of the panels in a field.
+#[derive(Clone, PartialEq)]
+pub struct Statistics {
+    pub counts: PanelMap<usize>,
+    /// The amount of panels that aren't empty.
+    pub total: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Statistics {
+    /// Counts every panel in a field.
+    pub fn new(field: &Field) -> Statistics {
+        let mut counts = PanelMap::new(|_| 0);
+
+        for (x, y) in field.iter() {
+            counts[field.get(x, y).kind] += 1;
+        }
+
+        Statistics {
+            total: field.width() * field.height() - counts[PanelKind::Empty],
+            counts,
+            width: field.width(),
+            height: field.height(),
+        }
+    }
+
+    /// Gets the percentage of non-empty panels that are of a kind.
+    pub fn percentage(&self, kind: PanelKind) -> f32 {
+        if self.total > 0 {
+            self.counts[kind] as f32 / self.total as f32 * 100.
+        } else {
+            0.
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use yew::prelude::*;
+
+use citrus_common::PanelKind;
+
+use super::Statistics;
+use crate::editor::assets;
+
+/// Panel statistics component.
+pub struct StatisticsPanel {
+    props: Props,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub stats: Statistics,
+}
+
+impl Component for StatisticsPanel {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        StatisticsPanel { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let stats = &self.props.stats;
+
+        html! {
+            <div class="statistics-panel">
+                <h3>{ "Statistics" }</h3>
+                <p>
+                    { format!("{} × {}, {} panels", stats.width, stats.height, stats.total) }
+                </p>
+                <ul>
+                    {
+                        for stats.counts.iter()
+                            .filter(|&(kind, &count)| kind != PanelKind::Empty && count > 0)
+                            .map(|(kind, &count)| self.view_count(kind, count))
+                    }
+                </ul>
+            </div>
+        }
+    }
+}
+
+impl StatisticsPanel {
+    fn view_count(&self, kind: PanelKind, count: usize) -> Html {
+        let label = match assets::panel_source(kind) {
+            Some(src) => html! { <img src=src title=format!("{:?}", kind) /> },
+            None => html! { { format!("{:?}", kind) } },
+        };
+
+        html! {
+            <li>
+                { label }
+                <span class="count">{ count }</span>
+                <span class="percentage">
+                    { format!("{:.1}%", self.props.stats.percentage(kind)) }
+                </span>
+            </li>
+        }
+    }
+}
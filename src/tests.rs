@@ -1,59 +1,539 @@
-use std::cell::Cell;
-
-use crate::enum_map::EnumMap;
+use crate::editor::assets::{DirectionMap, PanelAtlas, PanelMap};
+use crate::editor::keymap::{Binding, Command, Keymap};
+use crate::editor::scene::{self, Scene};
+use crate::gl::{atlas, Rect};
+use crate::render::{SoftwareBatch, SoftwareRenderer, SoftwareTexture};
+use crate::stats::Statistics;
+use crate::validate::{self, Problem, Rules, Validation};
 use citrus_common::PanelKind;
+use citrus_core::{Brush, Direction, EditorView, Selection, Tool};
+use na::{Matrix4, Vector2, Vector3};
 
 #[test]
-pub fn test_enum_map() {
-    // test allocation
-    let mut panel_map = EnumMap::<PanelKind, String>::new(|kind| match kind {
-        PanelKind::Bonus => String::from("awesome"),
-        _ => String::new(),
-    });
+pub fn test_keymap() {
+    let mut keymap = Keymap::default();
+
+    assert_eq!(
+        keymap.get(&Binding::new("1")),
+        Some(Command::SelectPanel(0))
+    );
+    assert_eq!(keymap.get(&Binding::new("Z").ctrl()), Some(Command::Undo));
+    assert_eq!(
+        keymap.get(&Binding::new("z").ctrl().shift()),
+        Some(Command::Redo)
+    );
+    assert_eq!(keymap.get(&Binding::new("z")), None);
+
+    // rebinding a key replaces its old command
+    keymap.bind(Binding::new("b"), Command::SelectTool(Tool::Bucket));
+    assert_eq!(
+        keymap.get(&Binding::new("b")),
+        Some(Command::SelectTool(Tool::Bucket))
+    );
+
+    keymap.unbind(&Binding::new("b"));
+    assert_eq!(keymap.get(&Binding::new("b")), None);
+
+    // rebinding a command replaces its old keys
+    keymap.rebind(Command::Delete, Binding::new("x"));
+    assert_eq!(keymap.get(&Binding::new("Delete")), None);
+    assert_eq!(keymap.get(&Binding::new("Backspace")), None);
+    assert_eq!(keymap.keys(Command::Delete).count(), 1);
+
+    assert_eq!(Binding::new("z").ctrl().shift().to_string(), "Ctrl+Shift+Z");
+    assert_eq!(Binding::new("ArrowUp").to_string(), "↑");
+    assert!(Binding::new("Shift").is_modifier());
+
+    // every command that can be remapped has a key to start with
+    let defaults = Keymap::default();
+
+    for command in Command::all() {
+        assert!(
+            defaults.keys(command).next().is_some(),
+            "{} has no key",
+            command
+        );
+    }
+}
 
-    assert_eq!(panel_map[PanelKind::Bonus], "awesome");
-    assert_ne!(panel_map[PanelKind::Drop], "awesome");
+#[test]
+pub fn test_validate() {
+    let mut view = EditorView::default();
+
+    // a home panel leading to a warp, and a lone panel off to the side
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(0.5, 0.5),
+        Brush::Place(PanelKind::Home),
+    );
+    view.paint_line(
+        &Vector2::new(1.5, 0.5),
+        &Vector2::new(1.5, 0.5),
+        Brush::Place(PanelKind::Warp),
+    );
+    view.paint_line(
+        &Vector2::new(3.5, 0.5),
+        &Vector2::new(3.5, 0.5),
+        Brush::Place(PanelKind::Draw),
+    );
+    view.set_exits(view.auto_route(true));
+    // point the lone panel off of the field
+    view.toggle_exit((3, 0), Direction::Up);
+
+    let problems: Vec<_> = validate::validate(&view.field, &Rules::default())
+        .into_iter()
+        .map(|issue| (issue.problem, issue.cell))
+        .collect();
+
+    assert_eq!(
+        problems,
+        [
+            (
+                Problem::HomeCount {
+                    found: 1,
+                    expected: 4
+                },
+                Some((0, 0))
+            ),
+            (Problem::DeadEnd, Some((3, 0))),
+            (Problem::LonelyWarp, Some((1, 0))),
+            (Problem::Unreachable(1), Some((3, 0))),
+        ]
+    );
+
+    // a single player only needs the one home panel
+    let mut validation = Validation::new(&view.field, Rules { players: 1 });
+    assert_eq!(validation.issues().len(), 3);
+
+    // the issues only change along with the field
+    validation.update(&view.field.clone());
+    assert_eq!(validation.issues().len(), 3);
 
-    // test saving to field
-    panel_map[PanelKind::Draw] = String::from("pretty awesome");
+    view.paint_line(
+        &Vector2::new(3.5, 0.5),
+        &Vector2::new(3.5, 0.5),
+        Brush::Erase,
+    );
+    validation.update(&view.field);
 
-    assert_eq!(panel_map[PanelKind::Bonus], "awesome");
-    assert_ne!(panel_map[PanelKind::Drop], "awesome");
-    assert_eq!(panel_map[PanelKind::Draw], "pretty awesome");
+    let problems: Vec<_> = validation
+        .issues()
+        .iter()
+        .map(|issue| issue.problem.clone())
+        .collect();
+    assert_eq!(problems, [Problem::LonelyWarp]);
+
+    // picking more players checks the same field again
+    validation.set_rules(Rules::default());
+    assert_eq!(validation.issues().len(), 2);
 }
 
 #[test]
-pub fn test_enum_map_safety() {
-    use std::panic::{catch_unwind, AssertUnwindSafe};
+pub fn test_statistics() {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(2.5, 0.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+    view.paint_line(
+        &Vector2::new(0.5, 1.5),
+        &Vector2::new(0.5, 1.5),
+        Brush::Place(PanelKind::Drop),
+    );
 
-    // we use a special type to tell us if the the item was successfully
-    // dropped
-    struct DropTest<'a>(&'a Cell<usize>);
+    let stats = Statistics::new(&view.field);
 
-    impl<'a> Drop for DropTest<'a> {
-        fn drop(&mut self) {
-            self.0.set(self.0.get() + 1);
+    assert_eq!((stats.width, stats.height), (3, 2));
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.counts[PanelKind::Bonus], 3);
+    assert_eq!(stats.counts[PanelKind::Empty], 2);
+    assert_eq!(stats.percentage(PanelKind::Drop), 25.);
+}
+
+#[test]
+pub fn test_atlas_packing() {
+    let sizes = [(64, 64), (128, 32), (64, 64), (16, 100), (128, 128)];
+    let ((width, height), positions) = atlas::pack(&sizes);
+
+    let rects: Vec<_> = sizes
+        .iter()
+        .zip(&positions)
+        .map(|(&(w, h), &(x, y))| (x, y, x + w, y + h))
+        .collect();
+
+    for (i, &(x, y, far_x, far_y)) in rects.iter().enumerate() {
+        // everything fits, padding and all
+        assert!(x >= atlas::PADDING && y >= atlas::PADDING);
+        assert!(far_x + atlas::PADDING <= width && far_y + atlas::PADDING <= height);
+
+        // and nothing overlaps
+        for &(other_x, other_y, other_far_x, other_far_y) in &rects[i + 1..] {
+            assert!(far_x <= other_x || other_far_x <= x || far_y <= other_y || other_far_y <= y);
         }
     }
 
-    let drop_count = Cell::new(0);
+    assert_eq!(atlas::pack(&[]), ((0, 0), Vec::new()));
+}
+
+/// The colors panels are drawn with in rendering tests.
+const BONUS_COLOR: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+const DROP_COLOR: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// The colors arrows are drawn with in rendering tests.
+const UP_COLOR: [u8; 4] = [0x00, 0xff, 0x00, 0xff];
+const RIGHT_COLOR: [u8; 4] = [0xff, 0xff, 0x00, 0xff];
+const DOWN_COLOR: [u8; 4] = [0xff, 0x00, 0xff, 0xff];
+const LEFT_COLOR: [u8; 4] = [0x00, 0xff, 0xff, 0xff];
 
-    let result = catch_unwind(AssertUnwindSafe(|| {
-        EnumMap::<PanelKind, DropTest>::new(|kind| match kind {
-            PanelKind::Drop2x => panic!("should be removed imo"),
-            _ => DropTest(&drop_count),
-        });
-    }));
+/// Makes an atlas with a single colored pixel for bonus and drop panels, and
+/// for each arrow.
+fn test_atlas() -> PanelAtlas<SoftwareTexture> {
+    let colors = [
+        BONUS_COLOR,
+        DROP_COLOR,
+        UP_COLOR,
+        RIGHT_COLOR,
+        DOWN_COLOR,
+        LEFT_COLOR,
+    ];
+    let texture = SoftwareTexture::new(colors.len() as u32, 1, colors.concat());
+    let pixel = |x: usize| Rect::new(x as f32, 0., 1., 1.);
 
-    assert!(result.is_err());
-    assert!(drop_count.get() > 0);
+    let mut regions = PanelMap::new_empty();
+    regions[PanelKind::Bonus] = Some(pixel(0));
+    regions[PanelKind::Drop] = Some(pixel(1));
+
+    let arrows = DirectionMap::new(|direction| pixel(2 + direction as usize));
+
+    PanelAtlas::from_parts(texture, regions, arrows)
 }
 
-#[test]
-pub fn test_enum_map_iter() {
-    let panel_map = EnumMap::<PanelKind, u8>::new(|kind| kind as u8);
+/// Makes a 3x2 field with a row of bonus panels, and a drop panel under the
+/// first one, where each cell is 4 pixels and the field is 2 pixels from the
+/// corner.
+fn test_render_view() -> EditorView {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(2.5, 0.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+    view.paint_line(
+        &Vector2::new(0.5, 1.5),
+        &Vector2::new(0.5, 1.5),
+        Brush::Place(PanelKind::Drop),
+    );
+
+    view.view = Matrix4::new_translation(&Vector3::new(2., 2., 0.)) * Matrix4::new_scaling(4.);
+    view
+}
+
+/// Makes a 1x1 field with a bonus panel, where the cell is 10 pixels and the
+/// field is 5 pixels from the corner.
+fn test_panel_view() -> EditorView {
+    let mut view = EditorView::default();
+
+    view.paint_line(
+        &Vector2::new(0.5, 0.5),
+        &Vector2::new(0.5, 0.5),
+        Brush::Place(PanelKind::Bonus),
+    );
+
+    view.view = Matrix4::new_translation(&Vector3::new(5., 5., 0.)) * Matrix4::new_scaling(10.);
+    view
+}
+
+/// Draws a scene with the software renderer, as a character for each pixel.
+///
+/// Pixels that aren't any color in `palette` are drawn as `?`.
+fn render_ascii(
+    scene: &Scene<SoftwareTexture, SoftwareBatch>,
+    palette: &[([u8; 4], char)],
+) -> String {
+    let mut renderer =
+        SoftwareRenderer::new(scene.canvas_size.x as u32, scene.canvas_size.y as u32);
+    scene.draw(&mut renderer);
+
+    let target = renderer.into_target();
+    let mut ascii = String::new();
+
+    for y in 0..target.height() {
+        for x in 0..target.width() {
+            let pixel = target.pixel(x, y);
+
+            ascii.push(
+                palette
+                    .iter()
+                    .find(|&&(color, _)| color == pixel)
+                    .map(|&(_, c)| c)
+                    .unwrap_or('?'),
+            );
+        }
 
-    for (kind, value) in panel_map.iter() {
-        println!("{:?}: {:X}", kind, value);
+        ascii.push('\n');
     }
+
+    ascii
+}
+
+#[test]
+pub fn test_render_field() {
+    let atlas = test_atlas();
+    let view = test_render_view();
+
+    let scene = Scene {
+        view: &view,
+        atlas: &atlas,
+        panels: None,
+        canvas_size: Vector2::new(16., 12.),
+        hover: None,
+        pasting: false,
+        show_grid: false,
+    };
+
+    let palette = [
+        ([0xff, 0xff, 0xff, 0x00], '.'),
+        (BONUS_COLOR, 'B'),
+        (DROP_COLOR, 'D'),
+    ];
+
+    let expected = "\
+................
+................
+..BBBBBBBBBBBB..
+..BBBBBBBBBBBB..
+..BBBBBBBBBBBB..
+..BBBBBBBBBBBB..
+..DDDD..........
+..DDDD..........
+..DDDD..........
+..DDDD..........
+................
+................
+";
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
+
+    // a batch of the same panels looks the same
+    let batch: SoftwareBatch = scene::field_rects(&atlas, &view.field, na::zero()).collect();
+    let scene = Scene {
+        panels: Some(&batch),
+        ..scene
+    };
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
+}
+
+#[test]
+pub fn test_render_overlay() {
+    let atlas = test_atlas();
+    let mut view = test_render_view();
+
+    view.selection = Some(Selection {
+        x: 0,
+        y: 0,
+        width: 1,
+        height: 2,
+    });
+
+    let scene = Scene {
+        view: &view,
+        atlas: &atlas,
+        panels: None,
+        canvas_size: Vector2::new(16., 12.),
+        hover: Some(((2, 1), Brush::Place(PanelKind::Drop))),
+        pasting: false,
+        show_grid: false,
+    };
+
+    let palette = [
+        ([0xff, 0xff, 0xff, 0x00], '.'),
+        (BONUS_COLOR, 'B'),
+        (DROP_COLOR, 'D'),
+        // the selection outline covers the whole selection at this size
+        ([0x3d, 0x8b, 0xff, 0xff], 'S'),
+        // a see-through drop panel
+        ([0x7f, 0x7f, 0xff, 0x80], 'd'),
+    ];
+
+    let expected = "\
+................
+................
+..SSSSBBBBBBBB..
+..SSSSBBBBBBBB..
+..SSSSBBBBBBBB..
+..SSSSBBBBBBBB..
+..SSSS....dddd..
+..SSSS....dddd..
+..SSSS....dddd..
+..SSSS....dddd..
+................
+................
+";
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
+}
+
+#[test]
+pub fn test_render_exits() {
+    let atlas = test_atlas();
+    let mut view = test_panel_view();
+
+    view.toggle_exit((0, 0), Direction::Up);
+    view.toggle_exit((0, 0), Direction::Right);
+
+    let scene = Scene {
+        view: &view,
+        atlas: &atlas,
+        panels: None,
+        canvas_size: Vector2::new(20., 20.),
+        hover: None,
+        pasting: false,
+        show_grid: false,
+    };
+
+    let palette = [
+        ([0xff, 0xff, 0xff, 0x00], '.'),
+        (BONUS_COLOR, 'B'),
+        (UP_COLOR, 'U'),
+        (RIGHT_COLOR, 'R'),
+        (DOWN_COLOR, 'D'),
+        (LEFT_COLOR, 'L'),
+    ];
+
+    let expected = "\
+....................
+....................
+....................
+....................
+....................
+.....BBBUUUBBBB.....
+.....BBBUUUBBBB.....
+.....BBBUUUBBBB.....
+.....BBBBBBBRRR.....
+.....BBBBBBBRRR.....
+.....BBBBBBBRRR.....
+.....BBBBBBBBBB.....
+.....BBBBBBBBBB.....
+.....BBBBBBBBBB.....
+.....BBBBBBBBBB.....
+....................
+....................
+....................
+....................
+....................
+";
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
+    // the arrows are batched along with the panels, on top of them
+    let batch: SoftwareBatch = scene::field_rects(&atlas, &view.field, na::zero()).collect();
+    assert_eq!(batch.len(), 3);
+
+    let scene = Scene {
+        panels: Some(&batch),
+        ..scene
+    };
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
+}
+
+#[test]
+pub fn test_render_grid() {
+    let atlas = test_atlas();
+    // only the grid, without any panels or field bounds in the way
+    let mut view = EditorView::default();
+    view.view = test_panel_view().view;
+
+    let scene = Scene {
+        view: &view,
+        atlas: &atlas,
+        panels: None,
+        canvas_size: Vector2::new(20., 20.),
+        hover: None,
+        pasting: false,
+        show_grid: true,
+    };
+
+    let palette = [
+        ([0xff, 0xff, 0xff, 0x00], '.'),
+        ([0xdf, 0xdf, 0xdf, 0x20], 'g'),
+        // where two lines cross
+        ([0xc3, 0xc3, 0xc3, 0x3c], '+'),
+    ];
+
+    let expected = "\
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+gggg+ggggggggg+ggggg
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+gggg+ggggggggg+ggggg
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+....g.........g.....
+";
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
+}
+
+#[test]
+pub fn test_render_erase() {
+    let atlas = test_atlas();
+    let view = test_panel_view();
+
+    let scene = Scene {
+        view: &view,
+        atlas: &atlas,
+        panels: None,
+        canvas_size: Vector2::new(20., 20.),
+        hover: Some(((0, 0), Brush::Erase)),
+        pasting: false,
+        show_grid: false,
+    };
+
+    let palette = [
+        ([0xff, 0xff, 0xff, 0x00], '.'),
+        ([0xff, 0x30, 0x30, 0xff], 'E'),
+        // the red fill over the bonus panel
+        ([0xff, 0x12, 0x12, 0xff], 'e'),
+    ];
+
+    let expected = "\
+....................
+....................
+....................
+....................
+....................
+.....EEEEEEEEEE.....
+.....EEEEEEEEEE.....
+.....EEeeeeeeEE.....
+.....EEeeeeeeEE.....
+.....EEeeeeeeEE.....
+.....EEeeeeeeEE.....
+.....EEeeeeeeEE.....
+.....EEeeeeeeEE.....
+.....EEEEEEEEEE.....
+.....EEEEEEEEEE.....
+....................
+....................
+....................
+....................
+....................
+";
+
+    assert_eq!(render_ascii(&scene, &palette), expected);
 }
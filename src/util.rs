@@ -1,8 +1,17 @@
 use na::{Vector2, Vector3};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, CanvasRenderingContext2d, HtmlAnchorElement, HtmlCanvasElement, ImageData, Url,
+};
+use yew::callback::Callback;
+use yew::html::{Component, ComponentLink};
 
 use std::ops::Deref;
 
 /// An owned version of [`MouseEvent`].
+#[derive(Clone)]
 pub struct MouseEvent {
     pos: Vector2<f32>,
     buttons: MouseButtons,
@@ -48,7 +57,11 @@ impl From<&web_sys::MouseEvent> for MouseEvent {
         MouseEvent {
             pos: Vector2::new(e.offset_x() as f32, e.offset_y() as f32),
             buttons: MouseButtons(e.buttons()),
-            button: MouseButtons(1 << e.button()),
+            // pointer events use -1 when no button changed
+            button: MouseButtons(match e.button() {
+                button @ 0..=15 => 1 << button,
+                _ => 0,
+            }),
             modifiers: e.into(),
         }
     }
@@ -124,6 +137,123 @@ impl From<&web_sys::WheelEvent> for WheelEvent {
     }
 }
 
+/// Creates a callback for a `href="#"` link that sends a message, without
+/// following the link.
+///
+/// Following it would add a history entry, and clear the location hash.
+pub fn link_callback<COMP, F, M>(
+    link: &ComponentLink<COMP>,
+    function: F,
+) -> Callback<web_sys::MouseEvent>
+where
+    COMP: Component,
+    M: Into<COMP::Message>,
+    F: Fn(web_sys::MouseEvent) -> M + 'static,
+{
+    link.callback(move |ev: web_sys::MouseEvent| {
+        ev.prevent_default();
+        function(ev)
+    })
+}
+
+/// Prompts the browser to download some data as a file.
+pub fn download(filename: &str, data: &[u8]) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let blob = Blob::new_with_u8_array_sequence(&parts)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let anchor: HtmlAnchorElement = document.create_element("a")?.unchecked_into();
+
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+
+    // some browsers ignore clicks on links that aren't in the document
+    let body = document.body().unwrap();
+    body.append_child(&anchor)?;
+    anchor.click();
+    body.remove_child(&anchor)?;
+
+    // the download only starts after this, so the url has to live a little
+    // longer
+    let revoke = Closure::once_into_js(move || {
+        let _ = Url::revoke_object_url(&url);
+    });
+
+    web_sys::window()
+        .unwrap()
+        .set_timeout_with_callback(revoke.unchecked_ref())?;
+
+    Ok(())
+}
+
+/// Encodes RGBA pixels, row by row from the top, as a PNG image.
+///
+/// This lets the browser do the encoding through a 2D canvas.
+pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas: HtmlCanvasElement = document.create_element("canvas")?.unchecked_into();
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("2d canvas is not supported"))?
+        .unchecked_into();
+
+    let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(pixels), width, height)?;
+    context.put_image_data(&image, 0., 0.)?;
+
+    // the url is the image in base64
+    let url = canvas.to_data_url_with_type("image/png")?;
+    let data = url
+        .split_once(',')
+        .map(|(_, data)| data)
+        .unwrap_or_default();
+
+    base64::decode(data).map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+// `web_sys` only exposes the clipboard behind `web_sys_unstable_apis`, so we
+// bind the two functions we need ourselves.
+#[wasm_bindgen]
+extern "C" {
+    type Clipboard;
+
+    #[wasm_bindgen(method, js_name = writeText)]
+    fn write_text(this: &Clipboard, data: &str) -> js_sys::Promise;
+
+    #[wasm_bindgen(method, js_name = readText)]
+    fn read_text(this: &Clipboard) -> js_sys::Promise;
+}
+
+fn clipboard() -> Result<Clipboard, JsValue> {
+    let navigator = web_sys::window().unwrap().navigator();
+    let clipboard = js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard"))?;
+
+    if clipboard.is_undefined() {
+        Err(JsValue::from_str("clipboard is not supported"))
+    } else {
+        Ok(clipboard.unchecked_into())
+    }
+}
+
+/// Writes text to the clipboard.
+pub async fn write_clipboard(text: &str) -> Result<(), JsValue> {
+    JsFuture::from(clipboard()?.write_text(text))
+        .await
+        .map(|_| ())
+}
+
+/// Reads text from the clipboard.
+pub async fn read_clipboard() -> Result<String, JsValue> {
+    JsFuture::from(clipboard()?.read_text())
+        .await?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("clipboard does not contain text"))
+}
+
 fn boolean(b: bool) -> u8 {
     if b {
         u8::MAX
@@ -0,0 +1,275 @@
+//! Checks for whether a field can actually be played on.
+
+pub mod report;
+
+pub use report::ValidationReport;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+
+use citrus_common::field::Field;
+use citrus_common::PanelKind;
+use citrus_core::{Direction, Exits};
+
+/// The rules a field is checked against.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rules {
+    /// How many players play on the field; each one needs a home panel.
+    pub players: usize,
+}
+
+impl Rules {
+    /// The most players a game can have.
+    pub const MAX_PLAYERS: usize = 4;
+
+    /// How many home panels a field needs.
+    pub fn home_count(&self) -> usize {
+        self.players
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            players: Rules::MAX_PLAYERS,
+        }
+    }
+}
+
+/// The issues with a field, kept until the field changes.
+///
+/// Fields are only replaced when they are edited, so an unchanged [`Rc`]
+/// means the issues are still right.
+pub struct Validation {
+    rules: Rules,
+    field: Rc<Field>,
+    issues: Vec<Issue>,
+}
+
+impl Validation {
+    pub fn new(field: &Rc<Field>, rules: Rules) -> Validation {
+        Validation {
+            rules,
+            field: field.clone(),
+            issues: validate(field, &rules),
+        }
+    }
+
+    /// Validates a field again, if it isn't the one last validated.
+    pub fn update(&mut self, field: &Rc<Field>) {
+        if !Rc::ptr_eq(&self.field, field) {
+            *self = Validation::new(field, self.rules);
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Validates the same field again, against different rules.
+    pub fn set_rules(&mut self, rules: Rules) {
+        if self.rules != rules {
+            *self = Validation::new(&self.field, rules);
+        }
+    }
+
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+}
+
+/// Something wrong with a field.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Issue {
+    pub problem: Problem,
+    /// The cell the problem is at, if it is at any one cell.
+    pub cell: Option<(usize, usize)>,
+}
+
+/// The kinds of things that can be wrong with a field.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Problem {
+    /// There isn't a home panel for every player.
+    HomeCount { found: usize, expected: usize },
+    /// A panel can't be left.
+    NoExits,
+    /// A panel only has exits that lead off of the field.
+    DeadEnd,
+    /// A group of panels can't be reached from any home panel.
+    Unreachable(usize),
+    /// A warp panel is the only one on the field, so it has nowhere to warp
+    /// to.
+    LonelyWarp,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::HomeCount { found, expected } => write!(
+                f,
+                "There are {} home panels, but there should be {}",
+                found, expected
+            ),
+            Problem::NoExits => f.write_str("This panel has no exits"),
+            Problem::DeadEnd => f.write_str("Every exit of this panel leads off of the field"),
+            Problem::Unreachable(1) => f.write_str("This panel can't be reached from home"),
+            Problem::Unreachable(size) => {
+                write!(f, "These {} panels can't be reached from home", size)
+            }
+            Problem::LonelyWarp => f.write_str("This warp has nowhere to warp to"),
+        }
+    }
+}
+
+/// Finds everything wrong with a field, row by row.
+pub fn validate(field: &Field, rules: &Rules) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let homes: Vec<_> = cells(field, |kind| kind == PanelKind::Home).collect();
+    let warps: Vec<_> = cells(field, is_warp).collect();
+
+    if homes.len() != rules.home_count() {
+        issues.push(Issue {
+            problem: Problem::HomeCount {
+                found: homes.len(),
+                expected: rules.home_count(),
+            },
+            cell: homes.first().copied(),
+        });
+    }
+
+    for cell in cells(field, |kind| kind != PanelKind::Empty) {
+        let exits = exits(field, cell);
+
+        let problem = if exits.is_empty() {
+            Problem::NoExits
+        } else if exits
+            .iter()
+            .all(|direction| neighbour(field, cell, direction).is_none())
+        {
+            Problem::DeadEnd
+        } else {
+            continue;
+        };
+
+        issues.push(Issue {
+            problem,
+            cell: Some(cell),
+        });
+    }
+
+    if warps.len() == 1 {
+        issues.push(Issue {
+            problem: Problem::LonelyWarp,
+            cell: Some(warps[0]),
+        });
+    }
+
+    // only bother with reachability if there's somewhere to start from
+    if !homes.is_empty() {
+        issues.extend(unreachable(field, &homes, &warps));
+    }
+
+    issues
+}
+
+/// Finds every group of panels that can't be reached from a home panel.
+fn unreachable(field: &Field, homes: &[(usize, usize)], warps: &[(usize, usize)]) -> Vec<Issue> {
+    let index = |(x, y): (usize, usize)| y * field.width() + x;
+
+    // walk the field from every home panel, following exits
+    let mut reached = vec![false; field.width() * field.height()];
+    let mut queue: VecDeque<_> = homes.iter().copied().collect();
+
+    while let Some(cell) = queue.pop_front() {
+        if std::mem::replace(&mut reached[index(cell)], true) {
+            continue;
+        }
+
+        let kind = field.get(cell.0, cell.1).kind;
+
+        // a warp can go to any other warp
+        if is_warp(kind) {
+            queue.extend(warps.iter().copied());
+        }
+
+        queue.extend(
+            exits(field, cell)
+                .iter()
+                .filter_map(|direction| neighbour(field, cell, direction)),
+        );
+    }
+
+    // group whatever wasn't reached, so a big unreachable area is only
+    // reported once
+    let mut issues = Vec::new();
+
+    for cell in cells(field, |kind| kind != PanelKind::Empty) {
+        if reached[index(cell)] {
+            continue;
+        }
+
+        let mut size = 0;
+        let mut stack = vec![cell];
+
+        while let Some(cell) = stack.pop() {
+            if std::mem::replace(&mut reached[index(cell)], true) {
+                continue;
+            }
+
+            size += 1;
+            stack.extend(
+                Direction::ALL
+                    .iter()
+                    .filter_map(|&direction| neighbour(field, cell, direction)),
+            );
+        }
+
+        issues.push(Issue {
+            problem: Problem::Unreachable(size),
+            cell: Some(cell),
+        });
+    }
+
+    issues
+}
+
+/// Iterates over every cell with a panel that matches `filter`.
+fn cells<'a>(
+    field: &'a Field,
+    filter: impl Fn(PanelKind) -> bool + 'a,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    field
+        .iter()
+        .filter(move |&(x, y)| filter(field.get(x, y).kind))
+}
+
+fn exits(field: &Field, (x, y): (usize, usize)) -> Exits {
+    Exits::of(field.get(x, y))
+}
+
+/// Gets the neighbouring panel in a direction, if there is one.
+fn neighbour(
+    field: &Field,
+    (x, y): (usize, usize),
+    direction: Direction,
+) -> Option<(usize, usize)> {
+    let (dx, dy) = direction.offset();
+    let x = (x as isize + dx) as usize;
+    let y = (y as isize + dy) as usize;
+
+    // negative positions wrap around to huge ones, so this covers both sides
+    if x < field.width() && y < field.height() && field.get(x, y).kind != PanelKind::Empty {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+fn is_warp(kind: PanelKind) -> bool {
+    matches!(
+        kind,
+        PanelKind::Warp | PanelKind::WarpMove | PanelKind::WarpMove2x
+    )
+}
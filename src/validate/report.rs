@@ -0,0 +1,105 @@
+use yew::callback::Callback;
+use yew::prelude::*;
+
+use super::{Issue, Rules};
+use crate::util;
+
+/// Validation report component.
+pub struct ValidationReport {
+    link: ComponentLink<Self>,
+    props: Props,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub issues: Vec<Issue>,
+    /// How many players the field was checked for.
+    pub players: usize,
+    /// Called with the cell of an issue when it is clicked.
+    pub onselect: Callback<(usize, usize)>,
+    /// Called when a different number of players is picked.
+    pub onplayers: Callback<usize>,
+}
+
+pub enum Msg {
+    Select((usize, usize)),
+    PlayersChange(ChangeData),
+}
+
+impl Component for ValidationReport {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        ValidationReport { link, props }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Select(cell) => {
+                // bubble
+                self.props.onselect.emit(cell);
+            }
+            Msg::PlayersChange(ChangeData::Select(select)) => {
+                if let Ok(players) = select.value().parse() {
+                    self.props.onplayers.emit(players);
+                }
+            }
+            Msg::PlayersChange(_) => (),
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="validation-report">
+                <h3>{ format!("Issues ({})", self.props.issues.len()) }</h3>
+                <label>
+                    { "Players " }
+                    <select onchange=self.link.callback(Msg::PlayersChange)>
+                        { for (1..=Rules::MAX_PLAYERS).map(|players| self.view_players(players)) }
+                    </select>
+                </label>
+                <ul>
+                    { for self.props.issues.iter().map(|issue| self.view_issue(issue)) }
+                </ul>
+            </div>
+        }
+    }
+}
+
+impl ValidationReport {
+    fn view_players(&self, players: usize) -> Html {
+        html! {
+            <option value=players.to_string() selected=players == self.props.players>
+                { players }
+            </option>
+        }
+    }
+
+    fn view_issue(&self, issue: &Issue) -> Html {
+        match issue.cell {
+            Some(cell) => html! {
+                <li>
+                    <a href="#"
+                       onclick=util::link_callback(&self.link, move |_| Msg::Select(cell))>
+                        { format!("({}, {}) {}", cell.0, cell.1, issue.problem) }
+                    </a>
+                </li>
+            },
+            None => html! {
+                <li>{ issue.problem.to_string() }</li>
+            },
+        }
+    }
+}